@@ -0,0 +1,88 @@
+//! Arena trees against the boxed `Node` layout.
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rand::Rng;
+use thin_tree::{
+    tree::{count_branches, count_generations, count_leaves, count_nodes},
+    Node, Offspring, OffspringDistribution, RngStrategy, TreeGenerator,
+};
+
+const SEED: u64 = 7;
+
+/// A supercritical law, so every tree runs into the node limit.
+fn generator() -> TreeGenerator {
+    TreeGenerator::from(Offspring::Poisson { lambda: 1.5 })
+        .rng(RngStrategy::Fast)
+        .seed(SEED)
+        .max_nodes(1_000_000)
+}
+
+/// The same process as [`TreeGenerator::generate_with`], building boxed nodes.
+fn generate_boxed(generator: &TreeGenerator, rng: &mut impl Rng) -> Node {
+    let mut levels = vec![vec![2], vec![2, 2]];
+    let mut frontier = 4;
+    let mut nodes = 7;
+    while frontier > 0 {
+        let level: Vec<u32> = (0..frontier)
+            .map(|_| generator.offspring.sample(rng))
+            .collect();
+        let next = level.iter().map(|&k| k as u64).sum::<u64>();
+        if nodes + next > generator.max_nodes as u64 {
+            break;
+        }
+        levels.push(level);
+        nodes += next;
+        frontier = next as usize;
+    }
+    levels.push(vec![0; frontier]);
+    let mut below: Vec<Node> = Vec::new();
+    for level in levels.iter().rev() {
+        let mut children = below.into_iter();
+        below = level
+            .iter()
+            .map(|&k| match k {
+                0 => Node::Leaf,
+                k => Node::Branch(children.by_ref().take(k as usize).collect()),
+            })
+            .collect();
+    }
+    below.pop().unwrap()
+}
+
+fn layout(c: &mut Criterion) {
+    let generator = generator();
+    let tree = generator.generate().into_tree();
+    let node = tree.to_node();
+
+    let mut group = c.benchmark_group("layout");
+    group.sample_size(10);
+    group.bench_function("generate/arena", |b| {
+        b.iter(|| generator.generate_with(&mut generator.make_rng()))
+    });
+    group.bench_function("generate/boxed", |b| {
+        b.iter(|| generate_boxed(&generator, &mut generator.make_rng()))
+    });
+    group.bench_function("generate/counts_only", |b| {
+        b.iter(|| generator.simulate_with(&mut generator.make_rng()))
+    });
+    group.bench_function("count/arena", |b| b.iter(|| tree.counts()));
+    group.bench_function("count/boxed", |b| {
+        b.iter(|| {
+            (
+                count_leaves(&node),
+                count_branches(&node),
+                count_nodes(&node),
+                count_generations(&node),
+            )
+        })
+    });
+    group.bench_function("clone_drop/arena", |b| {
+        b.iter_batched(|| (), |_| tree.clone(), BatchSize::LargeInput)
+    });
+    group.bench_function("clone_drop/boxed", |b| {
+        b.iter_batched(|| (), |_| node.clone(), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+//! One pass for every metric against a walk per metric, with and without
+//! the rolls.
+use criterion::{criterion_group, criterion_main, Criterion};
+use thin_tree::{
+    tree::{count_branches, count_generations, count_leaves, count_nodes},
+    Node, Offspring, RngStrategy, Tree, TreeGenerator, TreeMetrics,
+};
+
+const SEED: u64 = 7;
+
+/// Four lines of one child each, as tall as the generation limit.
+fn tall() -> Tree {
+    TreeGenerator::from(Offspring::Table(vec![0.0, 1.0]))
+        .max_generations(3000)
+        .generate()
+        .into_tree()
+}
+
+/// A supercritical tree cut off at the node limit.
+fn bushy() -> Tree {
+    TreeGenerator::from(Offspring::Poisson { lambda: 1.5 })
+        .rng(RngStrategy::Fast)
+        .seed(SEED)
+        .max_nodes(100_000)
+        .generate()
+        .into_tree()
+}
+
+/// The rolls of boxed nodes, walking down from the root again for every
+/// generation, as the boxed layout had to.
+fn rolls_boxed(root: &Node, generations: u64) -> String {
+    let mut rolls = String::new();
+    for seek in 2..=generations {
+        let mut stack = vec![(root, 0)];
+        while let Some((node, gen)) = stack.pop() {
+            let children: &[Node] = match node {
+                Node::Leaf => &[],
+                Node::Branch(children) => children,
+            };
+            if gen == seek {
+                match children.len() {
+                    k @ 0..=9 => rolls.push(char::from(b'0' + k as u8)),
+                    k => rolls.push_str(&format!("[{k}]")),
+                }
+            } else {
+                stack.extend(children.iter().rev().map(|child| (child, gen + 1)));
+            }
+        }
+    }
+    rolls
+}
+
+fn metrics(c: &mut Criterion) {
+    let mut group = c.benchmark_group("metrics");
+    group.sample_size(10);
+    for (name, tree) in [("tall", tall()), ("bushy", bushy())] {
+        let node = tree.to_node();
+        group.bench_function(format!("separate/{name}"), |b| {
+            b.iter(|| {
+                let generations = count_generations(&node);
+                (
+                    count_leaves(&node),
+                    count_branches(&node),
+                    count_nodes(&node),
+                    rolls_boxed(&node, generations),
+                )
+            })
+        });
+        group.bench_function(format!("single_pass/{name}"), |b| {
+            b.iter(|| TreeMetrics::new(&tree))
+        });
+        // What `Sample::draw` measures of every tree.
+        group.bench_function(format!("without_rolls/{name}"), |b| {
+            b.iter(|| TreeMetrics::without_rolls(&tree))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, metrics);
+criterion_main!(benches);
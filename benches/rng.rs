@@ -0,0 +1,38 @@
+//! The fast and secure RNG strategies, with and without reseeding.
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::RngCore;
+use thin_tree::{RngStrategy, TreeGenerator};
+
+fn strategies() -> [(&'static str, TreeGenerator); 3] {
+    let generator = TreeGenerator::new(50, 100).seed(1);
+    [
+        ("fast", generator.clone().rng(RngStrategy::Fast)),
+        ("secure", generator.clone().rng(RngStrategy::Secure)),
+        (
+            "secure_reseeding",
+            generator.rng(RngStrategy::Secure).reseed(64 * 1024),
+        ),
+    ]
+}
+
+fn rng(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rng");
+    for (name, generator) in strategies() {
+        group.bench_function(format!("draws/{name}"), |b| {
+            let mut rng = generator.make_rng();
+            b.iter(|| (0..1000).fold(0, |acc: u64, _| acc ^ rng.next_u64()))
+        });
+        group.bench_function(format!("trees/{name}"), |b| {
+            let mut rng = generator.make_rng();
+            b.iter(|| {
+                (0..100)
+                    .map(|_| generator.generate_with(&mut rng).tree().counts().nodes)
+                    .sum::<u64>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rng);
+criterion_main!(benches);
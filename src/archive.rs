@@ -0,0 +1,378 @@
+//! A compact binary container for samples of trees.
+//!
+//! An archive is laid out as, with integers little-endian:
+//!
+//! - the magic bytes `THINTREE` and the format [`VERSION`] as a `u32`;
+//! - a `u8` that is 1 if the settings follow, then the offspring law as a
+//!   `u32` length and its text, the RNG as a `u8` (0 fast, 1 secure), and
+//!   the seed, `max_nodes`, `max_generations` and reseed threshold as `u64`,
+//!   `u32`, `u32` and `u64`;
+//! - one record per tree: a bit that is 1 if the tree was censored, then
+//!   each node in preorder as its number of children in unary, that many 1
+//!   bits and a 0, packed least significant bit first into `ceil(2n / 8)`
+//!   bytes for `n` nodes;
+//! - an index of the `u64` offset of every record;
+//! - a footer with the `u64` offset of the index, then the number of trees
+//!   and of censored trees as `u32`s.
+//!
+//! Version 1 records have no censored bit.
+use crate::{
+    csv::{self, LoadError, Progress},
+    generator::{Generated, RngStrategy, TreeGenerator},
+    offspring::Offspring,
+    tree::{from_levels, Tree},
+};
+use rayon::prelude::*;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"THINTREE";
+
+/// The archive format written by this version.
+pub const VERSION: u32 = 2;
+
+/// Bytes in the footer that closes an archive.
+const FOOTER: u64 = 16;
+
+/// Writes trees to an archive one at a time, then the index on
+/// [`finish`](ArchiveWriter::finish).
+pub struct ArchiveWriter<W: Write> {
+    out: W,
+    /// Bytes written so far.
+    pos: u64,
+    offsets: Vec<u64>,
+    censored: u32,
+}
+impl ArchiveWriter<BufWriter<File>> {
+    /// Starts an archive in a new file at `path`.
+    pub fn create(path: impl AsRef<Path>, origin: Option<&TreeGenerator>) -> io::Result<Self> {
+        ArchiveWriter::new(BufWriter::new(File::create(path)?), origin)
+    }
+}
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive on `out` with the header for `origin`.
+    pub fn new(mut out: W, origin: Option<&TreeGenerator>) -> io::Result<Self> {
+        let mut header = MAGIC.to_vec();
+        header.extend(VERSION.to_le_bytes());
+        match origin {
+            None => header.push(0),
+            Some(generator) => {
+                let law = generator.offspring.to_string();
+                header.push(1);
+                header.extend((law.len() as u32).to_le_bytes());
+                header.extend(law.as_bytes());
+                header.push(match generator.rng {
+                    RngStrategy::Fast => 0,
+                    RngStrategy::Secure => 1,
+                });
+                header.extend(generator.seed.to_le_bytes());
+                header.extend(generator.max_nodes.to_le_bytes());
+                header.extend(generator.max_generations.to_le_bytes());
+                header.extend(generator.reseed.to_le_bytes());
+            }
+        }
+        out.write_all(&header)?;
+        Ok(ArchiveWriter {
+            out,
+            pos: header.len() as u64,
+            offsets: Vec::new(),
+            censored: 0,
+        })
+    }
+    /// Appends `tree`, marked as censored or not.
+    pub fn push(&mut self, tree: &Tree, censored: bool) -> io::Result<()> {
+        self.push_record(&encode(tree, censored), censored)
+    }
+    fn push_record(&mut self, record: &[u8], censored: bool) -> io::Result<()> {
+        self.out.write_all(record)?;
+        self.offsets.push(self.pos);
+        self.pos += record.len() as u64;
+        self.censored += censored as u32;
+        Ok(())
+    }
+    /// Writes the index and footer, returning the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        for offset in &self.offsets {
+            self.out.write_all(&offset.to_le_bytes())?;
+        }
+        self.out.write_all(&self.pos.to_le_bytes())?;
+        self.out
+            .write_all(&(self.offsets.len() as u32).to_le_bytes())?;
+        self.out.write_all(&self.censored.to_le_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// The record of one tree: its censored bit, then its bit-packed preorder
+/// child counts.
+fn encode(tree: &Tree, censored: bool) -> Vec<u8> {
+    let mut record = vec![0; (2 * tree.nodes() as usize).div_ceil(8)];
+    record[0] = censored as u8;
+    let mut bit = 1;
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        for _ in 0..tree.child_count(node) {
+            record[bit / 8] |= 1 << (bit % 8);
+            bit += 1;
+        }
+        // The 0 closing the run is already there.
+        bit += 1;
+        stack.extend(tree.children(node).rev());
+    }
+    record
+}
+
+/// Rebuilds a tree from its record, which ends where the root's subtree
+/// does, with whether it was censored if the record has the bit.
+///
+/// Nodes of one generation come in the same order in preorder as in level
+/// order, so the depth of each node is all it takes to sort them into
+/// generations.
+fn decode(bits: &[u8], flagged: bool) -> Result<(Tree, bool), LoadError> {
+    let bit = |i: usize| {
+        bits.get(i / 8)
+            .map(|byte| byte >> (i % 8) & 1 == 1)
+            .ok_or(LoadError::Format("archived tree ends early"))
+    };
+    let mut levels: Vec<Vec<u32>> = Vec::new();
+    // Children still to come of each node on the path from the root.
+    let mut pending: Vec<u32> = Vec::new();
+    let censored = flagged && bit(0)?;
+    let mut i = flagged as usize;
+    loop {
+        while pending.last() == Some(&0) {
+            pending.pop();
+        }
+        if !levels.is_empty() && pending.is_empty() {
+            break;
+        }
+        let depth = pending.len();
+        if let Some(siblings) = pending.last_mut() {
+            *siblings -= 1;
+        }
+        let mut children = 0;
+        while bit(i)? {
+            children += 1;
+            i += 1;
+        }
+        i += 1;
+        if levels.len() == depth {
+            levels.push(Vec::new());
+        }
+        levels[depth].push(children);
+        pending.push(children);
+    }
+    if i.div_ceil(8) != bits.len() {
+        return Err(LoadError::Format("archived tree ends before its record"));
+    }
+    Ok((from_levels(&levels), censored))
+}
+
+/// An archive opened for reading trees at random.
+pub struct Archive<R> {
+    reader: R,
+    version: u32,
+    /// The settings from the header, if it has them.
+    pub origin: Option<TreeGenerator>,
+    /// How many of the trees were censored.
+    pub censored: u32,
+    offsets: Vec<u64>,
+    /// Where the trees end and the index starts.
+    index: u64,
+}
+impl Archive<BufReader<File>> {
+    /// Opens the archive at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Archive::new(BufReader::new(File::open(path)?))
+    }
+}
+impl<R: Read + Seek> Archive<R> {
+    /// Reads the header and index of the archive in `reader`.
+    pub fn new(mut reader: R) -> Result<Self, LoadError> {
+        let short = |e: io::Error| match e.kind() {
+            io::ErrorKind::UnexpectedEof => LoadError::Format("archive ends early"),
+            _ => LoadError::Io(e),
+        };
+        if read::<8>(&mut reader).map_err(short)? != *MAGIC {
+            return Err(LoadError::Format("not an archive"));
+        }
+        let version = u32::from_le_bytes(read(&mut reader).map_err(short)?);
+        if !(1..=VERSION).contains(&version) {
+            return Err(LoadError::Format("unknown archive version"));
+        }
+        let origin = match read::<1>(&mut reader).map_err(short)? {
+            [0] => None,
+            [1] => Some(read_settings(&mut reader).map_err(short)??),
+            _ => return Err(LoadError::Format("bad settings flag")),
+        };
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < FOOTER {
+            return Err(LoadError::Format("archive ends early"));
+        }
+        reader.seek(SeekFrom::Start(end - FOOTER))?;
+        let index = u64::from_le_bytes(read(&mut reader)?);
+        let len = u32::from_le_bytes(read(&mut reader)?);
+        let censored = u32::from_le_bytes(read(&mut reader)?);
+        if index.checked_add(8 * len as u64 + FOOTER) != Some(end) {
+            return Err(LoadError::Format("archive index does not fit the file"));
+        }
+        reader.seek(SeekFrom::Start(index))?;
+        let offsets = (0..len)
+            .map(|_| read(&mut reader).map(u64::from_le_bytes))
+            .collect::<io::Result<Vec<_>>>()?;
+        if offsets.iter().any(|&offset| offset >= index) {
+            return Err(LoadError::Format("archive index points past the trees"));
+        }
+        Ok(Archive {
+            reader,
+            version,
+            origin,
+            censored,
+            offsets,
+            index,
+        })
+    }
+    /// Number of trees.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+    /// Whether the archive holds no trees.
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+    /// Reads tree `row`, counting from 1 as sample files do.
+    ///
+    /// Version 1 records only say they were censored in an archive of one.
+    pub fn tree(&mut self, row: u32) -> Result<Generated, LoadError> {
+        let i = row.checked_sub(1).ok_or(LoadError::NoRow(row))? as usize;
+        let offset = *self.offsets.get(i).ok_or(LoadError::NoRow(row))?;
+        let end = self.offsets.get(i + 1).copied().unwrap_or(self.index);
+        if end < offset {
+            return Err(LoadError::Format("archive index is out of order"));
+        }
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut bits = vec![0; (end - offset) as usize];
+        self.reader.read_exact(&mut bits)?;
+        let (tree, censored) = decode(&bits, self.version >= 2)?;
+        let censored = censored || (self.version < 2 && self.len() == 1 && self.censored == 1);
+        Ok(Generated::new(tree, censored))
+    }
+}
+
+fn read<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+fn read_settings(reader: &mut impl Read) -> io::Result<Result<TreeGenerator, LoadError>> {
+    let len = u32::from_le_bytes(read(reader)?);
+    if len > 1 << 16 {
+        return Ok(Err(LoadError::Format("bad offspring law in header")));
+    }
+    let mut law = vec![0; len as usize];
+    reader.read_exact(&mut law)?;
+    let rng = match read(reader)? {
+        [0] => RngStrategy::Fast,
+        [1] => RngStrategy::Secure,
+        _ => return Ok(Err(LoadError::Format("bad RNG in header"))),
+    };
+    let seed = u64::from_le_bytes(read(reader)?);
+    let max_nodes = u32::from_le_bytes(read(reader)?);
+    let max_generations = u32::from_le_bytes(read(reader)?);
+    let reseed = u64::from_le_bytes(read(reader)?);
+    let Some(law) = String::from_utf8(law)
+        .ok()
+        .and_then(|law| law.parse::<Offspring>().ok())
+    else {
+        return Ok(Err(LoadError::Format("bad offspring law in header")));
+    };
+    Ok(Ok(TreeGenerator::from(law)
+        .rng(rng)
+        .seed(seed)
+        .max_nodes(max_nodes)
+        .max_generations(max_generations)
+        .reseed(reseed)))
+}
+
+/// Writes `sample_size` trees drawn from `generator` across all threads,
+/// as [`csv::write_samples`] does, to an archive at `path`, returning how
+/// many of them were censored.
+pub fn write_samples(
+    path: impl AsRef<Path>,
+    generator: &TreeGenerator,
+    sample_size: u32,
+) -> io::Result<u32> {
+    let mut archive = ArchiveWriter::create(path, Some(generator))?;
+    for start in (0..sample_size).step_by(csv::CHUNK as usize) {
+        let end = sample_size.min(start.saturating_add(csv::CHUNK));
+        let records: Vec<(bool, Vec<u8>)> = (start..end)
+            .into_par_iter()
+            .map_init(
+                || generator.make_rng(),
+                |rng, i| {
+                    rng.set_stream(i as u64);
+                    let tree = generator.generate_with(rng);
+                    (tree.is_censored(), encode(tree.tree(), tree.is_censored()))
+                },
+            )
+            .collect();
+        for (censored, record) in records {
+            archive.push_record(&record, censored)?;
+        }
+    }
+    let censored = archive.censored;
+    archive.finish()?;
+    Ok(censored)
+}
+
+/// Packs the trees of the CSV file at `from`, a sample file with rolls or a
+/// single tree, into an archive at `to`, returning how many there are.
+pub fn from_csv(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<u32, LoadError> {
+    let file = csv::TreeFile::read(from)?;
+    if !file.has_rolls() {
+        return Err(LoadError::Format("file holds counts only, without rolls"));
+    }
+    let mut archive = ArchiveWriter::create(to, file.origin.as_ref())?;
+    for tree in file.trees() {
+        let tree = tree?;
+        archive.push(tree.tree(), tree.is_censored())?;
+    }
+    // Sample files before version 4 only count their censored rows.
+    if file.is_samples() && file.version < 4 {
+        archive.censored = file.censored_rows.unwrap_or(0);
+    }
+    let len = archive.offsets.len() as u32;
+    archive.finish()?;
+    Ok(len)
+}
+
+/// Unpacks the archive at `from` into a CSV sample file with rolls at `to`,
+/// returning how many trees there are.
+pub fn to_csv(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<u32, LoadError> {
+    let mut archive = Archive::open(from)?;
+    let mut out = BufWriter::new(File::create(to)?);
+    writeln!(
+        out,
+        "{}{}",
+        csv::header(archive.origin.as_ref(), false),
+        csv::SAMPLE_COLUMNS
+    )?;
+    let len = archive.len() as u32;
+    for row in 1..=len {
+        writeln!(out, "{}", csv::sample_row(row, &archive.tree(row)?))?;
+    }
+    let progress = Progress {
+        rows: len,
+        target: len,
+        added: len,
+        censored: archive.censored,
+        interrupted: false,
+    };
+    writeln!(out, "{}", csv::trailer(&progress))?;
+    out.flush()?;
+    Ok(len)
+}
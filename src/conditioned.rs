@@ -0,0 +1,225 @@
+//! Trees conditioned on having exactly a given number of nodes or leaves.
+//!
+//! Past the two fixed generations, a tree is a forest of four Galton–Watson
+//! trees. Listing its nodes' child counts in level order gives a sequence
+//! `x_1..x_n` whose running sum of `x_i - 1` stays above -4 until it ends
+//! there, and every such sequence is a forest. For laws of at most two
+//! children, the size fixes how many nodes have 0, 1 and 2 children, up to
+//! one free count with a law of its own. Drawing that count, and shuffling
+//! the nodes, gives the child counts of a conditioned forest in a random
+//! order; by the cycle lemma, exactly 4 of the `n` rotations of the order
+//! are forests, and one of them picked at random is the tree. Nothing is
+//! rejected, so a tree of `n` nodes takes `O(n)` time.
+use crate::{generator::TreeGenerator, offspring::OffspringDistribution, tree::Tree};
+use rand::prelude::*;
+
+/// Trees in the forest below the two fixed generations.
+const ROOTS: u64 = 4;
+
+/// The exact size to condition a tree on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Nodes(u32),
+    Leaves(u32),
+}
+impl Size {
+    /// The number of nodes or leaves.
+    pub fn count(self) -> u32 {
+        match self {
+            Size::Nodes(count) | Size::Leaves(count) => count,
+        }
+    }
+    /// The size of the smallest tree, in the same unit: the fixed
+    /// generations, with the four nodes of generation 2 as leaves.
+    pub fn min(self) -> Size {
+        match self {
+            Size::Nodes(_) => Size::Nodes(3 + ROOTS as u32),
+            Size::Leaves(_) => Size::Leaves(ROOTS as u32),
+        }
+    }
+}
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Size::Nodes(nodes) => write!(f, "{nodes} nodes"),
+            Size::Leaves(leaves) => write!(f, "{leaves} leaves"),
+        }
+    }
+}
+
+impl TreeGenerator {
+    /// Draws the tree of the current seed, conditioned on `size`.
+    pub fn generate_sized(&self, size: Size) -> Result<Tree, SizeError> {
+        self.generate_sized_with(size, &mut self.make_rng())
+    }
+    /// Draws a tree from the offspring law conditioned on `size`, from
+    /// `rng`. The limits on nodes and generations do not apply.
+    pub fn generate_sized_with(&self, size: Size, rng: &mut impl Rng) -> Result<Tree, SizeError> {
+        if self.offspring.max_children().is_none_or(|k| k > 2) {
+            return Err(SizeError::Law);
+        }
+        if size.count() < size.min().count() {
+            return Err(SizeError::TooSmall { min: size.min() });
+        }
+        let p = [0, 1, 2].map(|k| self.offspring.probability(k));
+        let (zeros, ones, twos) = match size {
+            Size::Nodes(nodes) => by_nodes(nodes as u64 - 3, p, rng)?,
+            Size::Leaves(leaves) => by_leaves(leaves as u64, p, rng)?,
+        };
+        let nodes = zeros + ones + twos;
+        if nodes + 3 > u32::MAX as u64 {
+            return Err(SizeError::TooLarge);
+        }
+        let mut counts = vec![0u32; zeros as usize];
+        counts.resize((zeros + ones) as usize, 1);
+        counts.resize(nodes as usize, 2);
+        counts.shuffle(rng);
+        rotate(&mut counts, rng);
+        Ok(forest_tree(&counts))
+    }
+}
+
+/// How many of the `n` nodes of the forest have 0, 1 and 2 children.
+///
+/// With `c` nodes of two children, there are `c + 4` leaves and `n - 4 - 2c`
+/// nodes of one, and `c` has weight `p0^a p1^b p2^c / (a! b! c!)` for those
+/// counts `a`, `b` and `c`.
+fn by_nodes(
+    n: u64,
+    [p0, p1, p2]: [f64; 3],
+    rng: &mut impl Rng,
+) -> Result<(u64, u64, u64), SizeError> {
+    let counts = |c: u64| (ROOTS + c, n - ROOTS - 2 * c, c);
+    let most = (n - ROOTS) / 2;
+    if p0 == 0.0 {
+        return Err(SizeError::Impossible);
+    }
+    let twos = if p1 == 0.0 {
+        // Every branch has two children.
+        if (n - ROOTS) % 2 == 1 || (most > 0 && p2 == 0.0) {
+            return Err(SizeError::Impossible);
+        }
+        most
+    } else if p2 == 0.0 {
+        0
+    } else {
+        // The log weight of each `c`, relative to that of 0, from the ratios
+        // of neighbouring weights.
+        let step = (p0 * p2 / (p1 * p1)).ln();
+        let weights = || {
+            (0..=most).scan(0.0, move |weight: &mut f64, c| {
+                let current = *weight;
+                let (a, b, _) = counts(c);
+                if c < most {
+                    *weight +=
+                        (b as f64 * (b - 1) as f64 / ((a + 1) as f64 * (c + 1) as f64)).ln() + step;
+                }
+                Some(current)
+            })
+        };
+        let top = weights().fold(f64::MIN, f64::max);
+        let total: f64 = weights().map(|w| (w - top).exp()).sum();
+        let mut target = rng.random::<f64>() * total;
+        weights()
+            .position(|w| {
+                target -= (w - top).exp();
+                target < 0.0
+            })
+            .map_or(most, |c| c as u64)
+    };
+    Ok(counts(twos))
+}
+
+/// How many nodes of the forest have 0, 1 and 2 children, with `leaves`
+/// leaves.
+///
+/// There are `leaves - 4` nodes of two children, and the number of nodes of
+/// one is negative binomial: the successes, each with probability `p1`,
+/// before `2 * leaves - 4` failures.
+fn by_leaves(
+    leaves: u64,
+    [p0, p1, p2]: [f64; 3],
+    rng: &mut impl Rng,
+) -> Result<(u64, u64, u64), SizeError> {
+    let twos = leaves - ROOTS;
+    if p0 == 0.0 || (twos > 0 && p2 == 0.0) {
+        return Err(SizeError::Impossible);
+    }
+    let mut ones = 0u64;
+    if p1 > 0.0 {
+        // Each failure comes after a geometric number of successes.
+        let scale = 1.0 / p1.ln();
+        for _ in 0..2 * leaves - ROOTS {
+            ones += ((-rng.random::<f64>()).ln_1p() * scale) as u64;
+            if ones > u32::MAX as u64 {
+                return Err(SizeError::TooLarge);
+            }
+        }
+    }
+    Ok((leaves, ones, twos))
+}
+
+/// Rotates child counts that add up to 4 fewer than there are into one of
+/// the 4 orders that list a forest, picked at random.
+///
+/// Those orders start just after the running sum of `x_i - 1` first reaches
+/// each of its 4 lowest values.
+fn rotate(counts: &mut [u32], rng: &mut impl Rng) {
+    let mut firsts = Vec::new();
+    let mut sum = 0i64;
+    for (i, &count) in counts.iter().enumerate() {
+        sum += count as i64 - 1;
+        if sum < -(firsts.len() as i64) {
+            firsts.push(i + 1);
+        }
+    }
+    let start = firsts[firsts.len() - 1 - rng.random_range(0..ROOTS as usize)];
+    counts.rotate_left(start);
+}
+
+/// The tree with the fixed generations over the forest whose level-order
+/// child counts are `counts`.
+fn forest_tree(counts: &[u32]) -> Tree {
+    let mut tree = Tree::empty();
+    for level in [&[2][..], &[2, 2]] {
+        tree.start_generation();
+        level.iter().for_each(|&children| tree.push(children));
+    }
+    let (mut pos, mut width) = (0, ROOTS as usize);
+    while width > 0 {
+        tree.start_generation();
+        let level = &counts[pos..pos + width];
+        level.iter().for_each(|&children| tree.push(children));
+        pos += width;
+        width = level.iter().map(|&k| k as usize).sum();
+    }
+    debug_assert_eq!(pos, counts.len());
+    tree
+}
+
+/// Why no tree of the requested size could be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeError {
+    /// The offspring law can give a node more than two children.
+    Law,
+    /// Every tree has at least `min`.
+    TooSmall { min: Size },
+    /// The offspring law gives no tree of this size.
+    Impossible,
+    /// The tree drawn would have more nodes than a tree can hold.
+    TooLarge,
+}
+impl std::fmt::Display for SizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizeError::Law => write!(
+                f,
+                "exact sizes need an offspring law of at most two children"
+            ),
+            SizeError::TooSmall { min } => write!(f, "every tree has at least {min}"),
+            SizeError::Impossible => write!(f, "the offspring law gives no tree of this size"),
+            SizeError::TooLarge => write!(f, "the tree would have too many nodes"),
+        }
+    }
+}
+impl std::error::Error for SizeError {}
@@ -0,0 +1,679 @@
+//! The CSV files written by the menu's export actions.
+use crate::{
+    generator::{Generated, TreeGenerator},
+    histogram::Histogram,
+    metrics::TreeMetrics,
+    offspring::Offspring,
+    profile::MeanProfile,
+    theory::Prediction,
+    tree::{Counts, RollsError, Tree},
+};
+use rayon::prelude::*;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Column names of a sample file, after its [`header`]: the row index,
+/// whether the tree was censored, then its [`record`].
+pub const SAMPLE_COLUMNS: &str = "0,censored,leaves,branches,nodes,generations,sackin,colless,\
+                                  cherries,path_length,strahler,width,rolls";
+
+/// Column names of a sample file of version 3, without the censored column.
+const SHAPE_COLUMNS: &str =
+    "0,leaves,branches,nodes,generations,sackin,colless,cherries,path_length,strahler,width,rolls";
+
+/// Column names of a sample file before version 3, without shape indices.
+const SIZE_COLUMNS: &str = "0,leaves,branches,nodes,generations,rolls";
+
+/// Column names of a sample file written without the trees' rolls.
+pub const COUNT_COLUMNS: &str = "0,censored,leaves,branches,nodes,generations";
+
+/// Column names of a counts-only sample file before version 4.
+const UNFLAGGED_COUNT_COLUMNS: &str = "0,leaves,branches,nodes,generations";
+
+/// The file format written by this version. Version 1 files have binary
+/// 0/1 rolls and no `version` in their header, files before version 3
+/// have no shape indices, and sample files before version 4 do not say
+/// which rows are censored.
+pub const VERSION: u32 = 4;
+
+/// The `# version=..,law=..,rng=..,seed=..,max_nodes=..,max_generations=..`
+/// line that opens every exported file, with the settings left out if the
+/// tree's `origin` is unknown. A `reseed` threshold marks trees the seed
+/// cannot reproduce.
+pub fn header(origin: Option<&TreeGenerator>, censored: bool) -> String {
+    format!(
+        "# version={VERSION}{}{}\n",
+        origin.map_or(String::new(), |generator| format!(
+            ",law={},rng={},seed={},max_nodes={},max_generations={}{}",
+            generator.offspring,
+            generator.rng,
+            generator.seed,
+            generator.max_nodes,
+            generator.max_generations,
+            if generator.is_reproducible() {
+                String::new()
+            } else {
+                format!(",reseed={}", generator.reseed)
+            }
+        )),
+        if censored { ",censored" } else { "" }
+    )
+}
+
+/// The counts, [`Shape`](crate::Shape) indices and rolls of one tree, as in
+/// the [`SAMPLE_COLUMNS`] after the censored flag.
+pub fn record(tree: &Tree) -> String {
+    let TreeMetrics {
+        counts,
+        shape,
+        rolls,
+        ..
+    } = TreeMetrics::new(tree);
+    format!(
+        "{},{},{},{},{},{},{},{rolls}",
+        count_record(&counts),
+        shape.sackin,
+        shape.colless,
+        shape.cherries,
+        shape.path_length,
+        shape.strahler,
+        shape.width(),
+    )
+}
+
+/// Row `index` of a sample file, as in [`SAMPLE_COLUMNS`].
+pub fn sample_row(index: u32, tree: &Generated) -> String {
+    format!(
+        "{index},{},{}",
+        tree.is_censored() as u8,
+        record(tree.tree())
+    )
+}
+
+/// `leaves,branches,nodes,generations`, for a tree that was not kept.
+pub fn count_record(counts: &Counts) -> String {
+    format!(
+        "{},{},{},{}",
+        counts.leaves, counts.branches, counts.nodes, counts.generations
+    )
+}
+
+/// Writes `sample_size` trees drawn from `generator` across all threads, as
+/// [`Sample::draw`](crate::stats::Sample::draw) does, to `path`, returning
+/// how many of them were censored.
+///
+/// Without `rolls`, only the counts are written, and the trees are never
+/// built.
+pub fn write_samples(
+    path: impl AsRef<Path>,
+    generator: &TreeGenerator,
+    sample_size: u32,
+    rolls: bool,
+) -> io::Result<u32> {
+    let cancel = AtomicBool::new(false);
+    stream_samples(path, generator, sample_size, rolls, &cancel, |_| {})
+        .map(|written| written.censored)
+}
+
+/// Rows drawn in parallel between writes and progress reports.
+pub(crate) const CHUNK: u32 = 1024;
+
+/// How far a streamed sample export got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Rows in the file.
+    pub rows: u32,
+    /// Rows the file should end up with.
+    pub target: u32,
+    /// Rows added by this run.
+    pub added: u32,
+    /// Censored trees among the rows.
+    pub censored: u32,
+    /// Whether the run stopped short of the target.
+    pub interrupted: bool,
+}
+
+/// Like [`write_samples`], writing rows through a buffer as they are drawn
+/// and reporting `progress` after each chunk of them.
+///
+/// Once `cancel` is set, no more rows are drawn. The file still ends with
+/// a [`trailer`] then, and [`resume_samples`] can finish it.
+pub fn stream_samples(
+    path: impl AsRef<Path>,
+    generator: &TreeGenerator,
+    sample_size: u32,
+    rolls: bool,
+    cancel: &AtomicBool,
+    progress: impl FnMut(&Progress),
+) -> io::Result<Progress> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "{}{}",
+        header(Some(generator), false),
+        if rolls { SAMPLE_COLUMNS } else { COUNT_COLUMNS }
+    )?;
+    let start = Progress {
+        rows: 0,
+        target: sample_size,
+        added: 0,
+        censored: 0,
+        interrupted: false,
+    };
+    append_rows(out, generator, start, rolls, cancel, progress)
+}
+
+/// Draws more sample file rows with `generator` until `progress` reaches
+/// its target or `cancel` is set, then writes the trailer.
+fn append_rows(
+    mut out: impl Write,
+    generator: &TreeGenerator,
+    mut progress: Progress,
+    rolls: bool,
+    cancel: &AtomicBool,
+    mut report: impl FnMut(&Progress),
+) -> io::Result<Progress> {
+    while progress.rows < progress.target {
+        if cancel.load(Ordering::Relaxed) {
+            progress.interrupted = true;
+            break;
+        }
+        let end = progress.target.min(progress.rows.saturating_add(CHUNK));
+        let rows: Vec<(bool, String)> = (progress.rows + 1..=end)
+            .into_par_iter()
+            .map_init(
+                || generator.make_rng(),
+                |rng, i| {
+                    rng.set_stream(i as u64 - 1);
+                    if rolls {
+                        let t = generator.generate_with(rng);
+                        (t.is_censored(), sample_row(i, &t) + "\n")
+                    } else {
+                        let t = generator.simulate_with(rng);
+                        (
+                            t.is_censored(),
+                            format!(
+                                "{i},{},{}\n",
+                                t.is_censored() as u8,
+                                count_record(&t.counts())
+                            ),
+                        )
+                    }
+                },
+            )
+            .collect();
+        for (censored, row) in rows {
+            progress.censored += censored as u32;
+            out.write_all(row.as_bytes())?;
+        }
+        progress.added += end - progress.rows;
+        progress.rows = end;
+        report(&progress);
+    }
+    writeln!(out, "{}", trailer(&progress))?;
+    out.flush()?;
+    Ok(progress)
+}
+
+/// The `# rows=..,censored=..` line that closes a sample file, with
+/// `interrupted` if it holds fewer rows than asked for.
+pub fn trailer(progress: &Progress) -> String {
+    format!(
+        "# rows={},censored={}{}",
+        progress.rows,
+        progress.censored,
+        if progress.interrupted {
+            ",interrupted"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Extends the sample file at `path` to `sample_size` rows, drawing them
+/// with the settings in its header, as [`stream_samples`] does.
+///
+/// Picks up after the last complete row, so it also finishes files whose
+/// run was killed before it could write a trailer.
+pub fn resume_samples(
+    path: impl AsRef<Path>,
+    sample_size: u32,
+    cancel: &AtomicBool,
+    progress: impl FnMut(&Progress),
+) -> Result<Progress, LoadError> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut reader = BufReader::new(&file);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let (version, origin, _) = parse_header(
+        line.strip_prefix('#')
+            .ok_or(LoadError::Format("sample file has no header"))?,
+    )
+    .map_err(LoadError::Format)?;
+    if version != VERSION {
+        return Err(LoadError::Format(
+            "only files of the current version can be resumed",
+        ));
+    }
+    let generator = origin.ok_or(LoadError::Format("header has no settings to resume"))?;
+    if !generator.is_reproducible() {
+        return Err(LoadError::Format("reseeded samples cannot be resumed"));
+    }
+    let mut offset = line.len() as u64;
+    line.clear();
+    reader.read_line(&mut line)?;
+    let rolls = match line.trim_end() {
+        SAMPLE_COLUMNS => true,
+        COUNT_COLUMNS => false,
+        _ => return Err(LoadError::Format("not a sample file")),
+    };
+    offset += line.len() as u64;
+    let (mut rows, mut censored) = (0, 0);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') || line.starts_with('#') {
+            break;
+        }
+        let mut fields = line.splitn(3, ',');
+        match fields.next().map(str::parse::<u32>) {
+            Some(Ok(index)) if index == rows + 1 => rows = index,
+            _ => return Err(LoadError::Format("sample rows are out of order")),
+        }
+        censored += parse_flag(fields.next())?;
+        offset += line.len() as u64;
+    }
+    drop(reader);
+    file.set_len(offset)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let start = Progress {
+        rows,
+        target: sample_size.max(rows),
+        added: 0,
+        censored,
+        interrupted: false,
+    };
+    Ok(append_rows(
+        BufWriter::new(file),
+        &generator,
+        start,
+        rolls,
+        cancel,
+        progress,
+    )?)
+}
+
+/// The censored column of a sample row, as 0 or 1.
+fn parse_flag(field: Option<&str>) -> Result<u32, LoadError> {
+    match field {
+        Some("0") => Ok(0),
+        Some("1") => Ok(1),
+        _ => Err(LoadError::Format("censored column is not 0 or 1")),
+    }
+}
+
+/// The rows and censored trees a [`trailer`] counts, if it is one.
+fn parse_trailer(fields: &str) -> Option<(u32, u32)> {
+    let (mut rows, mut censored) = (None, None);
+    for field in fields.trim().split(',') {
+        match field.split_once('=') {
+            Some(("rows", val)) => rows = val.parse().ok(),
+            Some(("censored", val)) => censored = val.parse().ok(),
+            _ => {}
+        }
+    }
+    rows.zip(censored)
+}
+
+/// Writes a single tree to `path`.
+///
+/// The rolls leave out the two fixed generations, so trees without them,
+/// such as some read from Newick, cannot be written.
+pub fn write_tree(
+    path: impl AsRef<std::path::Path>,
+    tree: &Tree,
+    origin: Option<&TreeGenerator>,
+    censored: bool,
+) -> std::io::Result<()> {
+    if !tree.has_fixed_generations() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "tree does not start with the two fixed generations",
+        ));
+    }
+    std::fs::write(
+        path,
+        format!("{}{}\n", header(origin, censored), record(tree)),
+    )
+}
+
+/// The [`header`] of a file that sums up `samples` trees drawn from
+/// `generator`, with their number.
+fn summary_header(generator: &TreeGenerator, samples: u64) -> String {
+    format!(
+        "{},samples={samples}\n",
+        header(Some(generator), false).trim_end()
+    )
+}
+
+/// Writes the bins of named histograms of `samples` trees drawn from
+/// `generator` to `path`, one row per bin.
+pub fn write_histograms(
+    path: impl AsRef<std::path::Path>,
+    histograms: &[(&str, Histogram)],
+    generator: &TreeGenerator,
+    samples: u64,
+) -> std::io::Result<()> {
+    let mut rows = summary_header(generator, samples) + "metric,from,to,count\n";
+    for (name, histogram) in histograms {
+        for (from, to, count) in histogram.bins() {
+            rows.push_str(&format!("{name},{from},{to},{count}\n"));
+        }
+    }
+    std::fs::write(path, rows)
+}
+
+/// Writes the mean generation profile of `samples` trees drawn from
+/// `generator` to `path`, one row per generation, with the width
+/// `prediction` expects.
+pub fn write_profile(
+    path: impl AsRef<std::path::Path>,
+    profile: &MeanProfile,
+    prediction: &Prediction,
+    generator: &TreeGenerator,
+    samples: u64,
+) -> std::io::Result<()> {
+    let mut rows = summary_header(generator, samples)
+        + "generation,leaves,branches,width,std_dev,lower,upper,expected\n";
+    for (gen, mean) in profile.generations.iter().enumerate() {
+        rows.push_str(&format!(
+            "{gen},{},{},{},{},{},{},{}\n",
+            mean.leaves,
+            mean.branches,
+            mean.width,
+            mean.std_dev,
+            mean.lower,
+            mean.upper,
+            prediction.width(gen as u32)
+        ));
+    }
+    std::fs::write(path, rows)
+}
+
+/// A file written by [`write_tree`] or [`write_samples`], read back.
+#[derive(Debug, Clone)]
+pub struct TreeFile {
+    /// The file format [`VERSION`].
+    pub version: u32,
+    /// The settings from the file's [`header`], if it has them.
+    pub origin: Option<TreeGenerator>,
+    /// Whether the header marks the tree as censored.
+    pub censored: bool,
+    /// Whether the trailer marks the samples as cut short.
+    pub interrupted: bool,
+    /// How many of the samples the trailer counts as censored, if it does.
+    pub censored_rows: Option<u32>,
+    samples: bool,
+    rolls: bool,
+    /// The index of each row, whether it is censored, and its record.
+    rows: Vec<(u32, bool, String)>,
+}
+impl TreeFile {
+    /// Reads and parses the file at `path`.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+    /// Parses the contents of a file, of any version.
+    pub fn parse(contents: &str) -> Result<Self, LoadError> {
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+        let mut line = lines.next().ok_or(LoadError::Format("empty file"))?;
+        let (mut version, mut origin, mut censored) = (1, None, false);
+        if let Some(fields) = line.strip_prefix('#') {
+            (version, origin, censored) = parse_header(fields).map_err(LoadError::Format)?;
+            line = lines
+                .next()
+                .ok_or(LoadError::Format("no tree after the header"))?;
+        }
+        let columns = [
+            SAMPLE_COLUMNS,
+            COUNT_COLUMNS,
+            SHAPE_COLUMNS,
+            SIZE_COLUMNS,
+            UNFLAGGED_COUNT_COLUMNS,
+        ];
+        if !columns.contains(&line) {
+            return Ok(TreeFile {
+                version,
+                origin,
+                censored,
+                interrupted: false,
+                censored_rows: None,
+                samples: false,
+                rolls: true,
+                rows: vec![(1, censored, line.to_string())],
+            });
+        }
+        let flagged = line.starts_with("0,censored,");
+        let (mut interrupted, mut censored_rows) = (false, None);
+        let rows = lines
+            .filter(|line| match line.strip_prefix('#') {
+                Some(trailer) => {
+                    interrupted |= trailer.trim().split(',').any(|f| f == "interrupted");
+                    censored_rows = parse_trailer(trailer).map(|(_, censored)| censored);
+                    false
+                }
+                None => true,
+            })
+            .map(|line| {
+                let (index, mut record) = line
+                    .split_once(',')
+                    .ok_or(LoadError::Format("sample row without an index"))?;
+                let index = index
+                    .parse()
+                    .map_err(|_| LoadError::Format("sample row index is not a number"))?;
+                let mut censored = false;
+                if flagged {
+                    let (flag, rest) = record.split_once(',').unwrap_or((record, ""));
+                    censored = parse_flag(Some(flag))? == 1;
+                    record = rest;
+                }
+                Ok((index, censored, record.to_string()))
+            })
+            .collect::<Result<_, LoadError>>()?;
+        Ok(TreeFile {
+            version,
+            origin,
+            censored,
+            interrupted,
+            censored_rows,
+            samples: true,
+            rolls: ![COUNT_COLUMNS, UNFLAGGED_COUNT_COLUMNS].contains(&line),
+            rows,
+        })
+    }
+    /// Whether this is a sample file, holding one row per tree.
+    pub fn is_samples(&self) -> bool {
+        self.samples
+    }
+    /// Whether the rows hold the trees' rolls, and not just their counts.
+    pub fn has_rolls(&self) -> bool {
+        self.rolls
+    }
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+    /// Whether the file has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Decodes the tree in the row with index `row`, checking its stored
+    /// counts. A single-tree file has only row 1.
+    ///
+    /// Rows of sample files before version 4 read as complete.
+    pub fn tree(&self, row: u32) -> Result<Generated, LoadError> {
+        let (_, censored, record) = self
+            .rows
+            .iter()
+            .find(|(index, ..)| *index == row)
+            .ok_or(LoadError::NoRow(row))?;
+        Ok(Generated::new(self.decode(record)?, *censored))
+    }
+    /// Decodes every tree, in file order.
+    pub fn trees(&self) -> impl Iterator<Item = Result<Generated, LoadError>> + '_ {
+        self.rows
+            .iter()
+            .map(|(_, censored, record)| Ok(Generated::new(self.decode(record)?, *censored)))
+    }
+    fn decode(&self, record: &str) -> Result<Tree, LoadError> {
+        if !self.rolls {
+            return Err(LoadError::Format("file holds counts only, without rolls"));
+        }
+        let fields: Vec<&str> = record.split(',').collect();
+        let (rolls, stored) = fields.split_last().unwrap();
+        match (self.version, stored.len()) {
+            (..=2, 4) | (3.., 10) => {}
+            (..=2, _) => {
+                return Err(LoadError::Format(
+                    "expected leaves,branches,nodes,generations,rolls",
+                ))
+            }
+            _ => {
+                return Err(LoadError::Format(
+                    "expected counts, shape indices and rolls",
+                ))
+            }
+        }
+        let tree = match self.version {
+            1 => Tree::from_binary_rolls(rolls)?,
+            _ => Tree::from_rolls(rolls)?,
+        };
+        let TreeMetrics { counts, shape, .. } = TreeMetrics::without_rolls(&tree);
+        let actual = [
+            counts.leaves,
+            counts.branches,
+            counts.nodes,
+            counts.generations,
+            shape.sackin,
+            shape.colless,
+            shape.cherries as u64,
+            shape.path_length,
+            shape.strahler as u64,
+            shape.width() as u64,
+        ];
+        let columns = SAMPLE_COLUMNS.split(',').skip(2);
+        for ((column, stored), actual) in columns.zip(stored).zip(actual) {
+            let stored = stored
+                .parse()
+                .map_err(|_| LoadError::Format("tree count is not a number"))?;
+            if stored != actual {
+                return Err(LoadError::Mismatch {
+                    column,
+                    stored,
+                    actual,
+                });
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/// Also reads the `n=..,m=..` headers written before offspring laws were
+/// selectable.
+pub(crate) fn parse_header(
+    fields: &str,
+) -> Result<(u32, Option<TreeGenerator>, bool), &'static str> {
+    let (mut version, mut censored, mut settings) = (1, false, false);
+    let (mut law, mut n, mut m, mut rng, mut seed) = (None, None, None, None, None);
+    let mut reseed = 0;
+    let (mut max_nodes, mut max_generations) = (None, None);
+    for field in fields.trim().split(',') {
+        settings |= matches!(
+            field.split_once('='),
+            Some(("law" | "n" | "m" | "rng" | "seed", _))
+        );
+        match field.split_once('=') {
+            Some(("version", val)) => {
+                version = val.parse().map_err(|_| "version is not a number")?
+            }
+            Some(("law", val)) => law = val.parse().ok(),
+            Some(("n", val)) => n = val.parse().ok(),
+            Some(("m", val)) => m = val.parse().ok(),
+            Some(("rng", val)) => rng = val.parse().ok(),
+            Some(("seed", val)) => seed = val.parse().ok(),
+            Some(("reseed", val)) => reseed = val.parse().map_err(|_| "reseed is not a number")?,
+            Some(("max_nodes", val)) => {
+                max_nodes = Some(val.parse().map_err(|_| "max_nodes is not a number")?)
+            }
+            Some(("max_generations", val)) => {
+                max_generations = Some(val.parse().map_err(|_| "max_generations is not a number")?)
+            }
+            None if field == "censored" => censored = true,
+            _ => {}
+        }
+    }
+    if version > VERSION {
+        return Err("file is from a newer version");
+    }
+    let law = law
+        .or(n.zip(m).map(|(n, m)| Offspring::BernoulliPair { n, m }))
+        .filter(|law| law.validate().is_ok());
+    let origin = match (law, rng, seed) {
+        (Some(law), Some(rng), Some(seed)) => {
+            let mut generator = TreeGenerator::from(law).rng(rng).seed(seed).reseed(reseed);
+            generator.max_nodes = max_nodes.unwrap_or(generator.max_nodes);
+            generator.max_generations = max_generations.unwrap_or(generator.max_generations);
+            Some(generator)
+        }
+        _ if settings => return Err("header needs a valid law, rng and seed"),
+        _ => None,
+    };
+    Ok((version, origin, censored))
+}
+
+/// Why an exported file could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Format(&'static str),
+    Rolls(RollsError),
+    /// A stored count disagrees with the decoded tree.
+    Mismatch {
+        column: &'static str,
+        stored: u64,
+        actual: u64,
+    },
+    NoRow(u32),
+}
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{e}"),
+            LoadError::Format(e) => write!(f, "malformed file: {e}"),
+            LoadError::Rolls(e) => write!(f, "bad rolls: {e}"),
+            LoadError::Mismatch {
+                column,
+                stored,
+                actual,
+            } => write!(
+                f,
+                "file says {stored} {column}, but the rolls give {actual}"
+            ),
+            LoadError::NoRow(row) => write!(f, "no sample with index {row}"),
+        }
+    }
+}
+impl std::error::Error for LoadError {}
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+impl From<RollsError> for LoadError {
+    fn from(e: RollsError) -> Self {
+        LoadError::Rolls(e)
+    }
+}
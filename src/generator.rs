@@ -0,0 +1,411 @@
+//! Seeded tree generation.
+use crate::{
+    offspring::{Offspring, OffspringDistribution},
+    tree::{Counts, Tree},
+};
+use rand::prelude::*;
+use rand::rngs::{OsRng, ReseedingRng};
+use rand_chacha::{ChaCha20Core, ChaCha20Rng, ChaCha8Rng};
+use std::cell::RefCell;
+
+/// Which RNG drives the coin flips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RngStrategy {
+    Fast,
+    Secure,
+}
+impl RngStrategy {
+    /// The RNG of this strategy, seeded with `seed`.
+    pub fn rng(self, seed: u64) -> TreeRng {
+        match self {
+            RngStrategy::Fast => TreeRng::Fast(ChaCha8Rng::seed_from_u64(seed)),
+            RngStrategy::Secure => TreeRng::Secure(ChaCha20Rng::seed_from_u64(seed)),
+        }
+    }
+}
+impl std::fmt::Display for RngStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RngStrategy::Fast => "Fast",
+            RngStrategy::Secure => "Secure",
+        })
+    }
+}
+impl std::str::FromStr for RngStrategy {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fast" => Ok(RngStrategy::Fast),
+            "secure" => Ok(RngStrategy::Secure),
+            _ => Err("RNG strategy must be fast or secure"),
+        }
+    }
+}
+
+/// The seeded RNG behind an [`RngStrategy`].
+pub enum TreeRng {
+    Fast(ChaCha8Rng),
+    Secure(ChaCha20Rng),
+    /// ChaCha20 keyed and periodically rekeyed from OS entropy, which no
+    /// seed can reproduce.
+    Reseeding(ThreadReseedingRng),
+}
+impl TreeRng {
+    /// Switches to the start of one of the 2^64 independent streams of the
+    /// same seed. A reseeding RNG has no seed, and just carries on.
+    pub fn set_stream(&mut self, stream: u64) {
+        match self {
+            TreeRng::Fast(rng) => {
+                rng.set_stream(stream);
+                rng.set_word_pos(0);
+            }
+            TreeRng::Secure(rng) => {
+                rng.set_stream(stream);
+                rng.set_word_pos(0);
+            }
+            TreeRng::Reseeding(_) => {}
+        }
+    }
+}
+thread_local! {
+    /// The reseeding RNG of this thread, with the byte count it rekeys
+    /// after.
+    static RESEEDING: RefCell<Option<(u64, ReseedingRng<ChaCha20Core, OsRng>)>> =
+        const { RefCell::new(None) };
+}
+
+/// A handle on the calling thread's reseeding RNG.
+///
+/// Every handle on a thread draws from the same RNG, which lives as long as
+/// the thread, so it rekeys only as often as its threshold says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadReseedingRng {
+    threshold: u64,
+}
+impl ThreadReseedingRng {
+    /// A handle on an RNG that rekeys after every `threshold` bytes.
+    pub fn new(threshold: u64) -> Self {
+        ThreadReseedingRng { threshold }
+    }
+    fn with<T>(self, f: impl FnOnce(&mut ReseedingRng<ChaCha20Core, OsRng>) -> T) -> T {
+        RESEEDING.with_borrow_mut(|slot| {
+            if slot
+                .as_ref()
+                .is_none_or(|(threshold, _)| *threshold != self.threshold)
+            {
+                let rng =
+                    ReseedingRng::new(self.threshold, OsRng).expect("OS entropy is unavailable");
+                *slot = Some((self.threshold, rng));
+            }
+            f(&mut slot.as_mut().unwrap().1)
+        })
+    }
+}
+impl RngCore for ThreadReseedingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.with(|rng| rng.next_u32())
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.with(|rng| rng.next_u64())
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.with(|rng| rng.fill_bytes(dst))
+    }
+}
+impl RngCore for TreeRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            TreeRng::Fast(rng) => rng.next_u32(),
+            TreeRng::Secure(rng) => rng.next_u32(),
+            TreeRng::Reseeding(rng) => rng.next_u32(),
+        }
+    }
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            TreeRng::Fast(rng) => rng.next_u64(),
+            TreeRng::Secure(rng) => rng.next_u64(),
+            TreeRng::Reseeding(rng) => rng.next_u64(),
+        }
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        match self {
+            TreeRng::Fast(rng) => rng.fill_bytes(dst),
+            TreeRng::Secure(rng) => rng.fill_bytes(dst),
+            TreeRng::Reseeding(rng) => rng.fill_bytes(dst),
+        }
+    }
+}
+
+/// The result of a generation run: a tree, or only its [`Counts`].
+///
+/// A tree whose next generation would go past the generator's limits is
+/// cut off before it, its unexpanded nodes left as leaves, and returned as
+/// `Censored`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Generated<T = Tree> {
+    Complete(T),
+    Censored(T),
+}
+impl<T> Generated<T> {
+    /// Whether the tree was cut off at a limit.
+    pub fn is_censored(&self) -> bool {
+        matches!(self, Generated::Censored(_))
+    }
+    pub(crate) fn new(value: T, censored: bool) -> Self {
+        if censored {
+            Generated::Censored(value)
+        } else {
+            Generated::Complete(value)
+        }
+    }
+}
+impl Generated {
+    /// The tree, complete or not.
+    pub fn tree(&self) -> &Tree {
+        match self {
+            Generated::Complete(tree) | Generated::Censored(tree) => tree,
+        }
+    }
+    /// Takes the tree, complete or not.
+    pub fn into_tree(self) -> Tree {
+        match self {
+            Generated::Complete(tree) | Generated::Censored(tree) => tree,
+        }
+    }
+}
+impl Generated<Counts> {
+    /// The counts, complete or not.
+    pub fn counts(&self) -> Counts {
+        match self {
+            Generated::Complete(counts) | Generated::Censored(counts) => *counts,
+        }
+    }
+}
+
+/// What a generation run fills in, one generation at a time.
+trait Grow {
+    fn start_generation(&mut self);
+    fn push(&mut self, children: u32);
+    /// Turns every node of the current generation into a leaf.
+    fn prune_generation(&mut self);
+    fn nodes(&self) -> u64;
+    fn generations(&self) -> u64;
+}
+impl Grow for Tree {
+    fn start_generation(&mut self) {
+        Tree::start_generation(self)
+    }
+    fn push(&mut self, children: u32) {
+        Tree::push(self, children)
+    }
+    fn prune_generation(&mut self) {
+        Tree::prune_generation(self)
+    }
+    fn nodes(&self) -> u64 {
+        Tree::nodes(self) as u64
+    }
+    fn generations(&self) -> u64 {
+        Tree::generations(self) as u64
+    }
+}
+/// The counts of a tree that is not kept, and of its current generation.
+#[derive(Default)]
+struct Tally {
+    counts: Counts,
+    started: u64,
+    width: u64,
+    leaves: u64,
+}
+impl Grow for Tally {
+    fn start_generation(&mut self) {
+        self.counts.generations = self.started;
+        self.started += 1;
+        (self.width, self.leaves) = (0, 0);
+    }
+    fn push(&mut self, children: u32) {
+        self.counts.nodes += 1;
+        self.width += 1;
+        if children == 0 {
+            self.counts.leaves += 1;
+            self.leaves += 1;
+        } else {
+            self.counts.branches += 1;
+        }
+    }
+    fn prune_generation(&mut self) {
+        self.counts.leaves += self.width - self.leaves;
+        self.counts.branches -= self.width - self.leaves;
+        self.leaves = self.width;
+    }
+    fn nodes(&self) -> u64 {
+        self.counts.nodes
+    }
+    fn generations(&self) -> u64 {
+        self.counts.generations
+    }
+}
+
+/// Settings for generating trees.
+///
+/// Fields can be set directly or through the builder methods.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeGenerator {
+    pub offspring: Offspring,
+    pub rng: RngStrategy,
+    pub seed: u64,
+    /// Trees with more nodes than this are censored.
+    pub max_nodes: u32,
+    /// Trees deeper than this are censored.
+    pub max_generations: u32,
+    /// With the secure RNG, rekey from OS entropy after this many bytes,
+    /// at the cost of reproducibility; 0 never does.
+    pub reseed: u64,
+}
+impl From<Offspring> for TreeGenerator {
+    /// A generator for `offspring` with a random seed, the fast RNG and
+    /// default limits.
+    fn from(offspring: Offspring) -> Self {
+        TreeGenerator {
+            offspring,
+            rng: RngStrategy::Fast,
+            seed: rand::random(),
+            max_nodes: 1_000_000,
+            max_generations: 10_000,
+            reseed: 0,
+        }
+    }
+}
+impl TreeGenerator {
+    /// A generator for the Bernoulli-pair law with branch probability
+    /// `n / m`, a random seed, the fast RNG and default limits.
+    pub fn new(n: u32, m: u32) -> Self {
+        Offspring::BernoulliPair { n, m }.into()
+    }
+    /// Sets the offspring law.
+    pub fn offspring(mut self, offspring: Offspring) -> Self {
+        self.offspring = offspring;
+        self
+    }
+    /// Sets the RNG strategy.
+    pub fn rng(mut self, rng: RngStrategy) -> Self {
+        self.rng = rng;
+        self
+    }
+    /// Sets the seed of the next tree.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    /// Sets the node limit past which trees are censored.
+    pub fn max_nodes(mut self, max_nodes: u32) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+    /// Sets the depth limit past which trees are censored.
+    pub fn max_generations(mut self, max_generations: u32) -> Self {
+        self.max_generations = max_generations;
+        self
+    }
+    /// Sets how many bytes the secure RNG draws before rekeying; 0 never
+    /// rekeys.
+    pub fn reseed(mut self, reseed: u64) -> Self {
+        self.reseed = reseed;
+        self
+    }
+    /// Whether the seed determines every tree, which reseeding rules out.
+    pub fn is_reproducible(&self) -> bool {
+        self.rng == RngStrategy::Fast || self.reseed == 0
+    }
+    /// Checks the offspring law's parameters.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        self.offspring.validate()
+    }
+    /// A fresh RNG for the current strategy and seed, or a handle on this
+    /// thread's reseeding one.
+    pub fn make_rng(&self) -> TreeRng {
+        if self.is_reproducible() {
+            self.rng.rng(self.seed)
+        } else {
+            TreeRng::Reseeding(ThreadReseedingRng::new(self.reseed))
+        }
+    }
+    /// The RNG of tree `index` of a sample: stream `index` of the seed.
+    ///
+    /// Tree 0 of a sample is the tree of [`generate`](Self::generate).
+    pub fn sample_rng(&self, index: u64) -> TreeRng {
+        let mut rng = self.make_rng();
+        rng.set_stream(index);
+        rng
+    }
+    /// Generates the tree determined by the current seed.
+    pub fn generate(&self) -> Generated {
+        self.generate_with(&mut self.make_rng())
+    }
+    /// Generates a tree drawing from `rng`, one generation at a time.
+    pub fn generate_with(&self, rng: &mut impl Rng) -> Generated {
+        self.generate_with_law(&self.offspring, rng)
+    }
+    /// Like [`generate_with`](Self::generate_with), with any offspring law
+    /// in place of [`offspring`](Self::offspring).
+    pub fn generate_with_law(
+        &self,
+        law: &impl OffspringDistribution,
+        rng: &mut impl Rng,
+    ) -> Generated {
+        let (tree, censored) = self.grow(law, rng, Tree::empty());
+        Generated::new(tree, censored)
+    }
+    /// The counts of the tree determined by the current seed, without
+    /// building it.
+    pub fn simulate(&self) -> Generated<Counts> {
+        self.simulate_with(&mut self.make_rng())
+    }
+    /// Runs the process of [`generate_with`](Self::generate_with), keeping
+    /// only the counts: it draws the same numbers, and gives the counts of
+    /// the same tree.
+    pub fn simulate_with(&self, rng: &mut impl Rng) -> Generated<Counts> {
+        let (tally, censored) = self.grow(&self.offspring, rng, Tally::default());
+        Generated::new(tally.counts, censored)
+    }
+    fn grow<G: Grow>(
+        &self,
+        law: &impl OffspringDistribution,
+        rng: &mut impl Rng,
+        mut tree: G,
+    ) -> (G, bool) {
+        for level in [&[2][..], &[2, 2]] {
+            tree.start_generation();
+            level.iter().for_each(|&children| tree.push(children));
+        }
+        let mut frontier = 4;
+        while frontier > 0 {
+            tree.start_generation();
+            if tree.generations() >= self.max_generations as u64 {
+                (0..frontier).for_each(|_| tree.push(0));
+                return (tree, true);
+            }
+            // Censor as soon as the next generation is known to be too
+            // big, before a node indexes children past the limit.
+            let end = tree.nodes() + frontier;
+            let mut next = 0u64;
+            for pushed in 0..frontier {
+                let children = law.sample(rng);
+                next += children as u64;
+                if end + next > self.max_nodes as u64 {
+                    (pushed..frontier).for_each(|_| tree.push(0));
+                    tree.prune_generation();
+                    return (tree, true);
+                }
+                tree.push(children);
+            }
+            frontier = next;
+        }
+        (tree, false)
+    }
+}
@@ -0,0 +1,186 @@
+//! Graphviz DOT and SVG pictures of trees.
+//!
+//! Nodes are coloured by generation, with the eight terminal colours that
+//! [`get_nodes_at_generation`](crate::render::get_nodes_at_generation) uses.
+//! Files open with a comment of the settings that generated the tree.
+use crate::{csv, generator::TreeGenerator, tree::Tree};
+
+/// Fill colours for generations 0 to 7, then again from 8: the standard
+/// xterm values of ANSI colours 30 to 37.
+pub const GENERATION_COLOURS: [&str; 8] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+];
+
+/// Pixels between neighbouring nodes, and between generations.
+const SPACING: f64 = 16.0;
+const LEVEL: f64 = 32.0;
+const RADIUS: f64 = 5.0;
+
+/// The `// version=..,law=..,seed=..` line that opens a DOT file, with the
+/// [`csv::header`] fields of the tree's `origin`.
+pub fn dot_header(origin: Option<&TreeGenerator>, censored: bool) -> String {
+    format!("//{}", &csv::header(origin, censored)[1..])
+}
+
+/// The `<!-- version=..,law=..,seed=.. -->` line that opens an SVG file,
+/// with the [`csv::header`] fields of the tree's `origin`.
+pub fn svg_header(origin: Option<&TreeGenerator>, censored: bool) -> String {
+    format!(
+        "<!--{} -->\n",
+        csv::header(origin, censored)[1..].trim_end()
+    )
+}
+
+impl Tree {
+    /// The tree as a Graphviz `digraph`, with nodes named after their
+    /// level-order index.
+    pub fn to_dot(&self) -> String {
+        let mut res = String::from(
+            "digraph tree {\n  \
+             node [shape=circle, label=\"\", style=filled, width=0.2];\n",
+        );
+        for gen in 0..=self.generations() {
+            for node in self.generation(gen) {
+                res.push_str(&format!(
+                    "  n{node} [fillcolor=\"{}\"];\n",
+                    GENERATION_COLOURS[gen as usize % 8]
+                ));
+            }
+        }
+        for node in 0..self.nodes() {
+            for child in self.children(node) {
+                res.push_str(&format!("  n{node} -> n{child};\n"));
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+    /// The tree drawn as an SVG image, root at the top, in the [`layout`].
+    pub fn to_svg(&self) -> String {
+        let xs = layout(self);
+        let width = xs.iter().fold(0.0f64, |max, &x| max.max(x));
+        let point = |node: u32, gen: u32| {
+            (
+                SPACING * (xs[node as usize] + 1.0),
+                LEVEL * (gen as f64 + 0.5),
+            )
+        };
+        let mut edges = String::new();
+        let mut nodes = String::new();
+        for gen in 0..=self.generations() {
+            for node in self.generation(gen) {
+                let (x, y) = point(node, gen);
+                for child in self.children(node) {
+                    let (cx, cy) = point(child, gen + 1);
+                    edges.push_str(&format!(
+                        "<line x1=\"{x}\" y1=\"{y}\" x2=\"{cx}\" y2=\"{cy}\"/>\n"
+                    ));
+                }
+                nodes.push_str(&format!(
+                    "<circle cx=\"{x}\" cy=\"{y}\" r=\"{RADIUS}\" fill=\"{}\"/>\n",
+                    GENERATION_COLOURS[gen as usize % 8]
+                ));
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n\
+             <g stroke=\"#333333\" stroke-width=\"1\">\n{edges}{nodes}</g>\n</svg>\n",
+            SPACING * (width + 2.0),
+            LEVEL * (self.generations() + 1) as f64,
+        )
+    }
+}
+
+/// The outline of a subtree: the x of its leftmost or rightmost node on
+/// each generation, deepest first, so that adding a parent pushes onto the
+/// end. Every x is `offset` too small, so that shifting is cheap.
+struct Contour {
+    xs: Vec<f64>,
+    offset: f64,
+}
+impl Contour {
+    /// The x of the node `depth` generations below the subtree's root.
+    fn at(&self, depth: usize) -> f64 {
+        self.xs[self.xs.len() - 1 - depth] + self.offset
+    }
+    /// Overwrites the top generations with those of `other`, shifted by `by`.
+    fn cover(&mut self, other: &Contour, by: f64) {
+        let len = self.xs.len();
+        for depth in 0..other.xs.len() {
+            self.xs[len - 1 - depth] = other.at(depth) + by - self.offset;
+        }
+    }
+}
+
+/// Tidy x positions of every node, in units of node spacing, leftmost at 0.
+///
+/// Reingold–Tilford style: each subtree is laid out on its own, siblings
+/// are pushed apart until their outlines are one unit apart on every
+/// generation, and parents are centred over their first and last children.
+/// Subtrees are laid out deepest first, without recursion.
+pub fn layout(tree: &Tree) -> Vec<f64> {
+    let mut relative = vec![0.0; tree.nodes() as usize];
+    let mut contours: Vec<Option<(Contour, Contour)>> = (0..tree.nodes()).map(|_| None).collect();
+    // Level order puts every node before its children.
+    for node in (0..tree.nodes()).rev() {
+        let mut children = tree.children(node);
+        let Some(first) = children.next() else {
+            contours[node as usize] = Some((
+                Contour {
+                    xs: vec![0.0],
+                    offset: 0.0,
+                },
+                Contour {
+                    xs: vec![0.0],
+                    offset: 0.0,
+                },
+            ));
+            continue;
+        };
+        let (mut left, mut right) = contours[first as usize].take().unwrap();
+        let mut last = 0.0;
+        for child in children {
+            let (child_left, mut child_right) = contours[child as usize].take().unwrap();
+            let shared = right.xs.len().min(child_left.xs.len());
+            let x = (0..shared).fold(f64::MIN, |x, depth| {
+                x.max(right.at(depth) - child_left.at(depth) + 1.0)
+            });
+            relative[child as usize] = x;
+            last = x;
+            if child_right.xs.len() >= right.xs.len() {
+                child_right.offset += x;
+                right = child_right;
+            } else {
+                right.cover(&child_right, x);
+            }
+            if child_left.xs.len() > left.xs.len() {
+                let mut deeper = child_left;
+                deeper.offset += x;
+                deeper.cover(&left, 0.0);
+                left = deeper;
+            }
+        }
+        // Centre the parent, and make the children relative to it.
+        let middle = last / 2.0;
+        for child in tree.children(node) {
+            relative[child as usize] -= middle;
+        }
+        for contour in [&mut left, &mut right] {
+            contour.offset -= middle;
+            contour.xs.push(-contour.offset);
+        }
+        contours[node as usize] = Some((left, right));
+    }
+    // Parents come first, so their positions are final when read.
+    let mut xs = vec![0.0; relative.len()];
+    for node in 0..tree.nodes() {
+        for child in tree.children(node) {
+            xs[child as usize] = xs[node as usize] + relative[child as usize];
+        }
+    }
+    // Shift by the summed positions, not the root's contour, so rounding
+    // cannot leave the leftmost node just off 0.
+    let min = xs.iter().fold(0.0f64, |min, &x| min.min(x));
+    xs.iter_mut().for_each(|x| *x -= min);
+    xs
+}
@@ -0,0 +1,44 @@
+//! Binned counts of a sampled metric.
+
+/// Counts of values in equal-width bins of whole numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// The smallest value of the first bin.
+    pub start: u64,
+    /// How many consecutive values each bin holds.
+    pub width: u64,
+    pub counts: Vec<u32>,
+}
+impl Histogram {
+    /// Spreads `values` over at most `bins` bins, from the smallest value to
+    /// the largest.
+    pub fn new(values: &[u64], bins: u32) -> Self {
+        let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+            return Histogram {
+                start: 0,
+                width: 1,
+                counts: Vec::new(),
+            };
+        };
+        // Every value from 0 to u64::MAX is one more than u64 holds.
+        let range = (max - min) as u128 + 1;
+        let width = range.div_ceil(bins.max(1) as u128).min(u64::MAX as u128) as u64;
+        let mut counts = vec![0; range.div_ceil(width as u128) as usize];
+        for &value in values {
+            counts[((value - min) / width) as usize] += 1;
+        }
+        Histogram {
+            start: min,
+            width,
+            counts,
+        }
+    }
+    /// Each bin as `(first value, last value, count)`.
+    pub fn bins(&self) -> impl Iterator<Item = (u64, u64, u32)> + '_ {
+        self.counts.iter().enumerate().map(|(i, &count)| {
+            let from = self.start as u128 + i as u128 * self.width as u128;
+            let to = (from + self.width as u128 - 1).min(u64::MAX as u128);
+            (from as u64, to as u64, count)
+        })
+    }
+}
@@ -0,0 +1,154 @@
+//! JSON files of trees, settings and stats, with the `serde` feature.
+//!
+//! Every file is an object with the schema `version` and its `kind`, so
+//! files of older versions can be upgraded as they load. Trees are written
+//! as the number of children of each node in level order, root first.
+use crate::{
+    generator::TreeGenerator,
+    stats::Stats,
+    tree::{from_levels, Node, Tree},
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// The current schema version.
+pub const VERSION: u32 = 1;
+
+/// Generator settings, with the number of trees drawn for stats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub generator: TreeGenerator,
+    pub sample_size: u32,
+}
+
+/// What a JSON file holds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Document {
+    Tree {
+        tree: Tree,
+        censored: bool,
+        /// The settings whose first draw the tree is, if known.
+        origin: Option<TreeGenerator>,
+    },
+    Settings(Settings),
+    Stats {
+        settings: Settings,
+        stats: Box<Stats>,
+    },
+}
+
+/// `document` as pretty-printed JSON, with the schema version.
+pub fn to_json(document: &Document) -> String {
+    #[derive(Serialize)]
+    struct Versioned<'a> {
+        version: u32,
+        #[serde(flatten)]
+        document: &'a Document,
+    }
+    serde_json::to_string_pretty(&Versioned {
+        version: VERSION,
+        document,
+    })
+    .expect("documents always serialise")
+}
+
+/// Reads a document of this or an older schema version.
+pub fn from_json(json: &str) -> Result<Document, JsonError> {
+    let value: Value = serde_json::from_str(json)?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(JsonError::Format("no schema version"))?;
+    let version = u32::try_from(version).map_err(|_| JsonError::Format("bad schema version"))?;
+    Ok(Document::deserialize(upgrade(value, version)?)?)
+}
+
+/// Rewrites a document of schema `version` into the current one.
+fn upgrade(value: Value, version: u32) -> Result<Value, JsonError> {
+    match version {
+        VERSION => Ok(value),
+        // Each past version gets an arm rewriting it into the next.
+        _ => Err(JsonError::Version(version)),
+    }
+}
+
+/// Writes `document` to `path`, as [`to_json`] gives it.
+pub fn write(path: impl AsRef<std::path::Path>, document: &Document) -> std::io::Result<()> {
+    std::fs::write(path, to_json(document) + "\n")
+}
+/// Reads the document at `path`, upgrading older versions.
+pub fn read(path: impl AsRef<std::path::Path>) -> Result<Document, JsonError> {
+    from_json(&std::fs::read_to_string(path)?)
+}
+
+impl Serialize for Tree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..self.nodes()).map(|node| self.child_count(node)))
+    }
+}
+impl<'de> Deserialize<'de> for Tree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let counts = Vec::<u32>::deserialize(deserializer)?;
+        let mut levels = Vec::new();
+        let (mut pos, mut width) = (0, 1);
+        while width > 0 {
+            let level = counts
+                .get(pos..pos + width)
+                .ok_or_else(|| D::Error::custom("tree ends in the middle of a generation"))?;
+            pos += width;
+            width = level.iter().map(|&k| k as usize).sum();
+            levels.push(level.to_vec());
+        }
+        if pos < counts.len() {
+            return Err(D::Error::custom(
+                "child counts left over after a generation of leaves",
+            ));
+        }
+        Ok(from_levels(&levels))
+    }
+}
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Tree::from(self).serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Tree::deserialize(deserializer).map(|tree| tree.to_node())
+    }
+}
+
+/// Why a JSON file could not be loaded.
+#[derive(Debug)]
+pub enum JsonError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Format(&'static str),
+    /// The file has a schema version this build does not know.
+    Version(u32),
+}
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::Io(e) => write!(f, "{e}"),
+            JsonError::Json(e) => write!(f, "malformed file: {e}"),
+            JsonError::Format(e) => write!(f, "malformed file: {e}"),
+            JsonError::Version(version) => write!(
+                f,
+                "unknown schema version {version}, this build reads up to {VERSION}"
+            ),
+        }
+    }
+}
+impl std::error::Error for JsonError {}
+impl From<std::io::Error> for JsonError {
+    fn from(e: std::io::Error) -> Self {
+        JsonError::Io(e)
+    }
+}
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        JsonError::Json(e)
+    }
+}
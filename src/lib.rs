@@ -0,0 +1,52 @@
+//! Random branching-process trees.
+//!
+//! Every tree starts with a root and two children, each with two children
+//! of its own. From the
+//! third generation on, each node draws its children from an
+//! [`OffspringDistribution`]. The default Bernoulli-pair law makes a node a
+//! leaf with probability `(m - n) / m`; otherwise it gets a first child, and
+//! a second one with probability `n / m`.
+//!
+//! ```
+//! use thin_tree::{RngStrategy, TreeGenerator};
+//!
+//! let tree = TreeGenerator::new(50, 100)
+//!     .rng(RngStrategy::Fast)
+//!     .seed(42)
+//!     .generate()
+//!     .into_tree();
+//! let counts = tree.counts();
+//! assert_eq!(counts.nodes, counts.leaves + counts.branches);
+//! assert!(counts.generations >= 2);
+//! ```
+#[macro_use]
+mod macros;
+pub mod archive;
+pub mod conditioned;
+pub mod csv;
+pub mod generator;
+pub mod graphics;
+pub mod histogram;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod metrics;
+pub mod newick;
+pub mod offspring;
+pub mod profile;
+pub mod render;
+pub mod shape;
+pub mod stats;
+pub mod theory;
+pub mod tree;
+
+pub use conditioned::{Size, SizeError};
+pub use generator::{Generated, RngStrategy, ThreadReseedingRng, TreeGenerator, TreeRng};
+pub use histogram::Histogram;
+pub use metrics::TreeMetrics;
+pub use newick::NewickError;
+pub use offspring::{Offspring, OffspringDistribution};
+pub use profile::{MeanProfile, Profile};
+pub use shape::Shape;
+pub use stats::{check_stats, Sample, Stats, Summary};
+pub use theory::Prediction;
+pub use tree::{Counts, Node, RollsError, Tree};
@@ -0,0 +1,22 @@
+/// Wraps `$thing` in the ANSI foreground colour `$tint` when `$colour` is set.
+#[macro_export]
+macro_rules! colour {
+    ($colour:expr, $thing:expr, $tint:expr) => {
+        if $colour {
+            format!("\x1B[3{}m{}\x1B[0m", $tint, $thing)
+        } else {
+            format!("{}", $thing)
+        }
+    };
+}
+/// Clears the terminal when `$colour` is set.
+#[macro_export]
+macro_rules! clear {
+    ($colour:expr) => {
+        if $colour {
+            format!("\x1B[2J\x1B[1;1H")
+        } else {
+            String::new()
+        }
+    };
+}
@@ -1,271 +1,739 @@
-use rand::{
-    prelude::*,
-    rngs::{OsRng, ReseedingRng},
+use clap::{Parser, Subcommand};
+use std::{
+    io::{stdout, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
 };
-use rand_chacha::{rand_core::SeedableRng, ChaCha20Core};
-use std::io::{stdout, Write};
-#[derive(Debug, Clone)]
-enum Node {
-    Leaf,
-    Branch(Box<(Node, Node)>),
+#[cfg(feature = "serde")]
+use thin_tree::json;
+use thin_tree::{
+    archive, check_stats, clear, colour,
+    csv::{self, Progress},
+    graphics, newick,
+    render::{
+        get_tree_rolls, get_tree_stats, print_histogram, print_prediction, print_profile,
+        print_progress, print_stats, print_stats_delta, print_tree,
+    },
+    stats::{Sample, Stats},
+    Generated, MeanProfile, Offspring, OffspringDistribution, Prediction, RngStrategy, Size,
+    SizeError, Tree, TreeGenerator,
+};
+/// Generate and analyse random branching-process trees.
+///
+/// Without a subcommand, starts the interactive menu with the given settings.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Numerator of the Bernoulli-pair branch probability n/m
+    #[arg(long, global = true, default_value_t = 50)]
+    n: u32,
+    /// Denominator of the Bernoulli-pair branch probability n/m
+    #[arg(long, global = true, default_value_t = 100)]
+    m: u32,
+    /// Offspring law, overriding --n and --m: bernoulli-pair:n/m, binomial:p,
+    /// geometric:p, poisson:λ or table:w0/w1/...
+    #[arg(long, global = true)]
+    law: Option<Offspring>,
+    /// Seed for the RNG [default: random]
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// RNG strategy: fast or secure
+    #[arg(long, global = true, default_value = "fast")]
+    rng: RngStrategy,
+    /// Number of trees drawn by stats
+    #[arg(long, global = true, default_value_t = 1000)]
+    sample_size: u32,
+    /// Percentiles reported by stats, besides the quartiles
+    #[arg(long, global = true, value_delimiter = ',', default_value = "10,90", value_parser = percentile)]
+    percentiles: Vec<f64>,
+    /// With --rng secure, rekey from OS entropy after this many bytes,
+    /// which makes runs irreproducible [default: never]
+    #[arg(long, global = true, default_value_t = 0, hide_default_value = true)]
+    reseed: u64,
+    /// Censor trees with more nodes than this
+    #[arg(long, global = true, default_value_t = 1_000_000)]
+    max_nodes: u32,
+    /// Censor trees deeper than this
+    #[arg(long, global = true, default_value_t = 10_000)]
+    max_generations: u32,
+    /// Threads used for sampling [default: one per core]
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+    /// Disable ANSI colours
+    #[arg(long, global = true)]
+    no_colour: bool,
+    /// Take the law, RNG, seed, limits and sample size from a JSON file of
+    /// settings, stats or a tree, instead of the other flags
+    #[cfg(feature = "serde")]
+    #[arg(long, global = true)]
+    settings: Option<PathBuf>,
 }
-impl TryFrom<Node> for (Node, Node) {
-    type Error = &'static str;
-    fn try_from(value: Node) -> Result<Self, Self::Error> {
-        match value {
-            Node::Branch(bx) => Ok((bx.0, bx.1)),
-            _ => Err("Not a branch"),
-        }
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a tree and show its stats
+    Generate {
+        #[command(flatten)]
+        size: SizeArgs,
+    },
+    /// Generate a tree and draw it
+    Print {
+        #[command(flatten)]
+        size: SizeArgs,
+    },
+    /// Collect stats of every metric over --sample-size trees
+    Stats {
+        /// Also write the settings and stats to this JSON file
+        #[cfg(feature = "serde")]
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+    /// Draw histograms of leaves, generations and nodes over --sample-size trees
+    Histogram {
+        /// Maximum number of bins
+        #[arg(long, default_value_t = 20)]
+        bins: u32,
+        /// Scale bars by the logarithm of the counts
+        #[arg(long)]
+        log: bool,
+        /// Also write the bins to this CSV file
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Draw the mean size of each generation over --sample-size trees,
+    /// against the expected growth
+    Profile {
+        /// Also write the profile to this CSV file
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write sampled trees to a CSV file, or a binary archive if it ends in
+    /// .ttb
+    Sample {
+        /// Number of trees
+        #[arg(long)]
+        count: u32,
+        #[arg(long)]
+        out: PathBuf,
+        /// Write only the counts, without building the trees
+        #[arg(long)]
+        counts_only: bool,
+        /// Carry on an interrupted file up to --count rows, with the
+        /// settings in its header
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Write one generated tree to a CSV file, or by extension to a Newick
+    /// (.nwk), Graphviz (.dot) or SVG (.svg) file
+    Export {
+        /// Defaults to leaves-branches-nodes-generations.csv
+        #[arg(long)]
+        out: Option<PathBuf>,
+        #[command(flatten)]
+        size: SizeArgs,
+        #[command(flatten)]
+        newick: NewickArgs,
+    },
+    /// Read a tree from a CSV or Newick file and write it to another, in any
+    /// format export writes
+    Convert {
+        input: PathBuf,
+        out: PathBuf,
+        /// Which tree of a sample file to read
+        #[arg(long, default_value_t = 1)]
+        row: u32,
+        #[command(flatten)]
+        newick: NewickArgs,
+    },
+    /// Convert a sample file between CSV and a binary archive (.ttb)
+    Repack { input: PathBuf, out: PathBuf },
+}
+#[derive(clap::Args)]
+struct NewickArgs {
+    /// Name Newick nodes after their level-order index
+    #[arg(long)]
+    labels: bool,
+    /// Give every Newick branch length 1
+    #[arg(long)]
+    lengths: bool,
+}
+#[derive(clap::Args)]
+struct SizeArgs {
+    /// Draw the tree conditioned on exactly this many nodes; needs a law of
+    /// at most two children
+    #[arg(long, conflicts_with = "leaves")]
+    nodes: Option<u32>,
+    /// Draw the tree conditioned on exactly this many leaves
+    #[arg(long)]
+    leaves: Option<u32>,
+}
+impl SizeArgs {
+    fn size(&self) -> Option<Size> {
+        self.nodes
+            .map(Size::Nodes)
+            .or(self.leaves.map(Size::Leaves))
     }
 }
-macro_rules! colour {
-    ($colour:expr, $thing:expr, $tint:expr) => {
-        if $colour {
-            format!("\x1B[3{}m{}\x1B[0m", $tint, $thing)
-        } else {
-            format!("{}", $thing)
-        }
-    };
+/// Generates the tree of the current seed, conditioned on `size` if given.
+fn generate(generator: &TreeGenerator, size: Option<Size>) -> Result<Generated, SizeError> {
+    match size {
+        Some(size) => generator.generate_sized(size).map(Generated::Complete),
+        None => Ok(generator.generate()),
+    }
+}
+fn rng_tint(rng: RngStrategy) -> u8 {
+    match rng {
+        RngStrategy::Fast => 2,
+        RngStrategy::Secure => 5,
+    }
 }
-macro_rules! clear {
-    ($colour:expr) => {
-        if $colour {
-            format!("\x1B[2J\x1B[1;1H")
+/// Set while samples are being exported, so Ctrl-C stops the export
+/// instead of the program.
+static EXPORTING: AtomicBool = AtomicBool::new(false);
+/// Set by Ctrl-C during an export.
+static CANCEL: AtomicBool = AtomicBool::new(false);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    ctrlc::set_handler(|| {
+        if EXPORTING.load(Ordering::SeqCst) {
+            CANCEL.store(true, Ordering::SeqCst);
         } else {
-            String::new()
+            std::process::exit(130);
+        }
+    })
+    .expect("Ctrl-C handler is already set");
+    let mut generator = TreeGenerator::from(
+        cli.law
+            .unwrap_or(Offspring::BernoulliPair { n: cli.n, m: cli.m }),
+    )
+    .rng(cli.rng)
+    .reseed(cli.reseed)
+    .max_nodes(cli.max_nodes)
+    .max_generations(cli.max_generations);
+    if let Some(seed) = cli.seed {
+        generator.seed = seed;
+    }
+    let sample_size = cli.sample_size;
+    #[cfg(feature = "serde")]
+    let (generator, sample_size) = match cli
+        .settings
+        .as_deref()
+        .map(|path| read_settings(path, sample_size))
+    {
+        Some(Ok(Some(settings))) => (settings.generator, settings.sample_size),
+        Some(Err(e)) => {
+            eprintln!("Error loading settings: {e}");
+            return ExitCode::FAILURE;
         }
+        _ => (generator, sample_size),
     };
-}
-fn check_stats(n: u32, m: u32, fast_rng: bool, sample_size: u32) -> (u32, u32, u32, u32, f64) {
-    let mut cur_vals = (0, 0, 0, 0, 0.0);
-    let mut leaves_vec = Vec::new();
-    for _ in 0..sample_size {
-        let tree = generate_tree(true, n, m, fast_rng);
-        let leaves = count_leaves(&tree);
-        leaves_vec.push(leaves);
-        cur_vals.2 = cur_vals.2.max(leaves);
-        cur_vals.0 = cur_vals.0.min(leaves);
-        if cur_vals.0 == 0 {
-            cur_vals.0 = leaves;
+    let colour = !cli.no_colour;
+    if let Some(threads) = cli.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            eprintln!("Error: {e}");
+            return ExitCode::FAILURE;
         }
     }
-    leaves_vec.sort();
-    cur_vals.1 = leaves_vec[leaves_vec.len() / 2];
-    cur_vals.3 = leaves_vec.iter().sum::<u32>() / leaves_vec.len() as u32;
-    cur_vals.4 = (leaves_vec
-        .iter()
-        .map(|x| (*x as f64 - cur_vals.3 as f64).powi(2))
-        .sum::<f64>()
-        / leaves_vec.len() as f64)
-        .sqrt();
-    cur_vals
-}
-fn get_tree_stats(tree: &Node) -> String {
-    format!(
-        "Leaves: {}\n\
-	 Branches: {}\n\
-	 Nodes: {}\n\
-	 Generations: {}",
-        count_leaves(tree),
-        count_branches(tree),
-        count_nodes(tree),
-        count_generations(tree),
-    )
-}
-fn get_tree_rolls(tree: &Node, generations: u32, colour: bool) -> String {
-    format!(
-        "Rolls: {}\n{}",
-        tree_to_string(tree, colour),
-        (0..=generations).fold(String::new(), |mut acc, i| {
-            acc.push_str(&format!(
-                "Gen {0:^1$}: {2}\n",
-                i,
-                format!("{generations}").len(),
-                get_nodes_at_generation(tree, i, 0, colour)
-            ));
-            acc
-        })
-    )
-}
-fn print_stats_delta(
-    prev_vals: (u32, u32, u32, u32, f64),
-    cur_vals: (u32, u32, u32, u32, f64),
-    colour: bool,
-) -> String {
-    format!(
-        "Tree stats:\
-	 \n\tMin = {} ({})\
-	 \n\tMedian = {} ({})\
-	 \n\tMax = {} ({})\
-	 \n\tAverage = {} ({})\
-	 \n\tσ = {} ({})",
-        match cur_vals.0.cmp(&prev_vals.0) {
-            std::cmp::Ordering::Less => colour!(colour, cur_vals.0, 1),
-            std::cmp::Ordering::Greater => colour!(colour, cur_vals.0, 2),
-            std::cmp::Ordering::Equal => colour!(colour, cur_vals.0, 4),
-        },
-        match cur_vals.0.cmp(&prev_vals.0) {
-            std::cmp::Ordering::Less => colour!(
-                colour,
-                format!(
-                    "↓{}",
-                    prev_vals.0.max(cur_vals.0) - prev_vals.0.min(cur_vals.0)
-                ),
-                1
-            ),
-            std::cmp::Ordering::Greater => colour!(
-                colour,
-                format!(
-                    "↑{}",
-                    prev_vals.0.max(cur_vals.0) - prev_vals.0.min(cur_vals.0)
-                ),
-                2
-            ),
-            std::cmp::Ordering::Equal => colour!(
-                colour,
-                format!(
-                    "={}",
-                    prev_vals.0.max(cur_vals.0) - prev_vals.0.min(cur_vals.0)
-                ),
-                4
-            ),
-        },
-        match cur_vals.1.cmp(&prev_vals.1) {
-            std::cmp::Ordering::Less => colour!(colour, cur_vals.1, 1),
-            std::cmp::Ordering::Greater => colour!(colour, cur_vals.1, 2),
-            std::cmp::Ordering::Equal => colour!(colour, cur_vals.1, 4),
-        },
-        match cur_vals.1.cmp(&prev_vals.1) {
-            std::cmp::Ordering::Less => colour!(
-                colour,
-                format!(
-                    "↓{}",
-                    prev_vals.1.max(cur_vals.1) - prev_vals.1.min(cur_vals.1)
-                ),
-                1
-            ),
-            std::cmp::Ordering::Greater => colour!(
-                colour,
-                format!(
-                    "↑{}",
-                    prev_vals.1.max(cur_vals.1) - prev_vals.1.min(cur_vals.1)
-                ),
-                2
-            ),
-            std::cmp::Ordering::Equal => colour!(
-                colour,
-                format!(
-                    "={}",
-                    prev_vals.1.max(cur_vals.1) - prev_vals.1.min(cur_vals.1)
-                ),
-                4
-            ),
-        },
-        match cur_vals.2.cmp(&prev_vals.2) {
-            std::cmp::Ordering::Less => colour!(colour, cur_vals.2, 1),
-            std::cmp::Ordering::Greater => colour!(colour, cur_vals.2, 2),
-            std::cmp::Ordering::Equal => colour!(colour, cur_vals.2, 4),
-        },
-        match cur_vals.2.cmp(&prev_vals.2) {
-            std::cmp::Ordering::Less => colour!(
-                colour,
-                format!(
-                    "↓{}",
-                    prev_vals.2.max(cur_vals.2) - prev_vals.2.min(cur_vals.2)
-                ),
-                1
-            ),
-            std::cmp::Ordering::Greater => colour!(
-                colour,
-                format!(
-                    "↑{}",
-                    prev_vals.2.max(cur_vals.2) - prev_vals.2.min(cur_vals.2)
-                ),
-                2
-            ),
-            std::cmp::Ordering::Equal => colour!(
-                colour,
-                format!(
-                    "={}",
-                    prev_vals.2.max(cur_vals.2) - prev_vals.2.min(cur_vals.2)
+    if let Err(e) = generator.validate() {
+        eprintln!("Error: {e}");
+        return ExitCode::from(2);
+    }
+    let Some(command) = cli.command else {
+        interactive(generator, sample_size, cli.percentiles, colour);
+        return ExitCode::SUCCESS;
+    };
+    match command {
+        Command::Generate { size } => {
+            let tree = match generate(&generator, size.size()) {
+                Ok(tree) => tree,
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            println!(
+                "{} (seed {})\n{}",
+                generated_status(&tree, colour),
+                colour!(colour, generator.seed, 4),
+                get_tree_stats(tree.tree())
+            );
+        }
+        Command::Print { size } => {
+            let tree = match generate(&generator, size.size()) {
+                Ok(tree) => tree,
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            println!(
+                "{}{}{}",
+                print_tree(tree.tree()),
+                get_tree_rolls(tree.tree(), colour),
+                get_tree_stats(tree.tree())
+            );
+        }
+        Command::Stats {
+            #[cfg(feature = "serde")]
+            json,
+        } => {
+            let stats = check_stats(&generator, sample_size, &cli.percentiles);
+            println!(
+                "Generated {} samples (seed {}), {} censored:\n{}\n{}",
+                colour!(colour, sample_size, 4),
+                colour!(colour, generator.seed, 4),
+                colour!(
+                    colour,
+                    stats.censored,
+                    if stats.censored > 0 { 3 } else { 4 }
                 ),
-                4
-            ),
-        },
-        match cur_vals.3.cmp(&prev_vals.3) {
-            std::cmp::Ordering::Less => colour!(colour, cur_vals.3, 1),
-            std::cmp::Ordering::Greater => colour!(colour, cur_vals.3, 2),
-            std::cmp::Ordering::Equal => colour!(colour, cur_vals.3, 4),
-        },
-        match cur_vals.3.cmp(&prev_vals.3) {
-            std::cmp::Ordering::Less => colour!(
-                colour,
-                format!(
-                    "↓{}",
-                    prev_vals.3.max(cur_vals.3) - prev_vals.3.min(cur_vals.3)
+                print_stats(&stats, colour),
+                print_prediction(&Prediction::new(&generator.offspring), &stats, colour)
+            );
+            #[cfg(feature = "serde")]
+            if let Some(path) = json {
+                let settings = json::Settings {
+                    generator,
+                    sample_size,
+                };
+                if let Err(e) = json::write(
+                    &path,
+                    &json::Document::Stats {
+                        settings,
+                        stats: Box::new(stats),
+                    },
+                ) {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Histogram { bins, log, out } => {
+            let sample = Sample::draw(&generator, sample_size);
+            let histograms = sample.histograms(bins);
+            println!(
+                "Generated {} samples (seed {}), {} censored:",
+                colour!(colour, sample_size, 4),
+                colour!(colour, generator.seed, 4),
+                colour!(
+                    colour,
+                    sample.censored,
+                    if sample.censored > 0 { 3 } else { 4 }
                 ),
-                1
-            ),
-            std::cmp::Ordering::Greater => colour!(
-                colour,
-                format!(
-                    "↑{}",
-                    prev_vals.3.max(cur_vals.3) - prev_vals.3.min(cur_vals.3)
+            );
+            for (name, histogram) in &histograms {
+                println!("{}", print_histogram(name, histogram, log, colour));
+            }
+            if let Some(out) = out {
+                if let Err(e) = csv::write_histograms(&out, &histograms, &generator, sample.samples)
+                {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Profile { out } => {
+            let sample = Sample::draw(&generator, sample_size);
+            let profile = MeanProfile::new(&sample.profiles);
+            let prediction = Prediction::new(&generator.offspring);
+            println!(
+                "Generated {} samples (seed {}), {} censored:\n{}",
+                colour!(colour, sample_size, 4),
+                colour!(colour, generator.seed, 4),
+                colour!(
+                    colour,
+                    sample.censored,
+                    if sample.censored > 0 { 3 } else { 4 }
                 ),
-                2
-            ),
-            std::cmp::Ordering::Equal => colour!(
-                colour,
+                print_profile(&profile, &prediction, colour)
+            );
+            if let Some(out) = out {
+                if let Err(e) =
+                    csv::write_profile(&out, &profile, &prediction, &generator, sample.samples)
+                {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Sample {
+            count,
+            out,
+            counts_only,
+            resume,
+        } => {
+            if TreeFormat::of(&out) == TreeFormat::Archive {
+                if counts_only || resume {
+                    eprintln!("Error: archives hold whole trees and cannot be resumed");
+                    return ExitCode::from(2);
+                }
+                match archive::write_samples(&out, &generator, count) {
+                    Ok(censored) => println!(
+                        "Wrote {} samples, {} censored, to file {}",
+                        colour!(colour, count, 4),
+                        colour!(colour, censored, if censored > 0 { 3 } else { 4 }),
+                        colour!(colour, out.display(), 4)
+                    ),
+                    Err(e) => {
+                        eprintln!("Error writing file: {e}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+                return ExitCode::SUCCESS;
+            }
+            let origin = (!resume).then_some(&generator);
+            let mut stderr = std::io::stderr();
+            let show = stderr.is_terminal();
+            match export_samples(&mut stderr, show, &out, origin, count, !counts_only, colour) {
+                Ok(progress) => {
+                    println!("{}", sample_status(&progress, &out.display(), colour));
+                    if progress.interrupted {
+                        return ExitCode::from(130);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Export { out, size, newick } => {
+            let size = size.size();
+            let tree = match generate(&generator, size) {
+                Ok(tree) => tree,
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            // A sized tree is not the one its seed generates.
+            let origin = size.is_none().then_some(&generator);
+            let out = out.unwrap_or_else(|| {
+                let counts = tree.tree().counts();
                 format!(
-                    "={}",
-                    prev_vals.3.max(cur_vals.3) - prev_vals.3.min(cur_vals.3)
+                    "{}-{}-{}-{}.csv",
+                    counts.leaves, counts.branches, counts.nodes, counts.generations
+                )
+                .into()
+            });
+            match write_tree(&out, &tree, origin, newick.labels, newick.lengths) {
+                Ok(()) => println!(
+                    "Wrote tree (seed {}) to file {}",
+                    colour!(colour, generator.seed, 4),
+                    colour!(colour, out.display(), 4)
                 ),
-                4
-            ),
-        },
-        match cur_vals.4.partial_cmp(&prev_vals.4).unwrap() {
-            std::cmp::Ordering::Less => colour!(colour, cur_vals.4, 1),
-            std::cmp::Ordering::Greater => colour!(colour, cur_vals.4, 2),
-            std::cmp::Ordering::Equal => colour!(colour, cur_vals.4, 4),
-        },
-        match cur_vals.4.partial_cmp(&prev_vals.4).unwrap() {
-            std::cmp::Ordering::Less => colour!(
-                colour,
-                format!(
-                    "↓{}",
-                    prev_vals.4.max(cur_vals.4) - prev_vals.4.min(cur_vals.4)
+                Err(e) => {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Repack { input, out } => {
+            let result = match (TreeFormat::of(&input), TreeFormat::of(&out)) {
+                (TreeFormat::Archive, TreeFormat::Csv) => archive::to_csv(&input, &out),
+                (TreeFormat::Csv, TreeFormat::Archive) => archive::from_csv(&input, &out),
+                _ => {
+                    eprintln!("Error: convert a .csv file to a .ttb archive or back");
+                    return ExitCode::from(2);
+                }
+            };
+            match result {
+                Ok(trees) => println!(
+                    "Wrote {} trees from {} to file {}",
+                    colour!(colour, trees, 4),
+                    colour!(colour, input.display(), 4),
+                    colour!(colour, out.display(), 4)
                 ),
-                1
-            ),
-            std::cmp::Ordering::Greater => colour!(
-                colour,
-                format!(
-                    "↑{}",
-                    prev_vals.4.max(cur_vals.4) - prev_vals.4.min(cur_vals.4)
+                Err(e) => {
+                    eprintln!("Error converting file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::Convert {
+            input,
+            out,
+            row,
+            newick,
+        } => {
+            let (tree, origin) = match read_tree(&input, row) {
+                Ok(read) => read,
+                Err(e) => {
+                    eprintln!("Error loading tree: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            match write_tree(&out, &tree, origin.as_ref(), newick.labels, newick.lengths) {
+                Ok(()) => println!(
+                    "Wrote tree from {} to file {}\n{}",
+                    colour!(colour, input.display(), 4),
+                    colour!(colour, out.display(), 4),
+                    get_tree_stats(tree.tree())
                 ),
-                2
-            ),
-            std::cmp::Ordering::Equal => colour!(
+                Err(e) => {
+                    eprintln!("Error writing file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+/// Streams samples to `path`, reporting progress on `out` if `show` is set,
+/// until done or stopped by Ctrl-C. Without an `origin`, resumes the file.
+fn export_samples(
+    out: &mut impl Write,
+    show: bool,
+    path: &Path,
+    origin: Option<&TreeGenerator>,
+    sample_size: u32,
+    rolls: bool,
+    colour: bool,
+) -> Result<Progress, csv::LoadError> {
+    EXPORTING.store(true, Ordering::SeqCst);
+    CANCEL.store(false, Ordering::SeqCst);
+    let start = Instant::now();
+    let report = |progress: &Progress| {
+        if show {
+            write!(
+                out,
+                "\r{}   ",
+                print_progress(progress, start.elapsed(), colour)
+            )
+            .unwrap();
+            out.flush().unwrap();
+        }
+    };
+    let result = match origin {
+        Some(generator) => {
+            csv::stream_samples(path, generator, sample_size, rolls, &CANCEL, report)
+                .map_err(csv::LoadError::from)
+        }
+        None => csv::resume_samples(path, sample_size, &CANCEL, report),
+    };
+    EXPORTING.store(false, Ordering::SeqCst);
+    if show {
+        writeln!(out).unwrap();
+    }
+    result
+}
+fn sample_status(progress: &Progress, path: &impl std::fmt::Display, colour: bool) -> String {
+    format!(
+        "Wrote {} samples, {} censored, to file {}{}",
+        colour!(colour, progress.rows, 4),
+        colour!(
+            colour,
+            progress.censored,
+            if progress.censored > 0 { 3 } else { 4 }
+        ),
+        colour!(colour, path, 4),
+        if progress.interrupted {
+            colour!(
                 colour,
                 format!(
-                    "={}",
-                    prev_vals.4.max(cur_vals.4) - prev_vals.4.min(cur_vals.4)
+                    "\nInterrupted {} samples short; resume the file to finish it",
+                    progress.target - progress.rows
                 ),
-                4
-            ),
-        },
+                3
+            )
+        } else {
+            String::new()
+        }
     )
 }
-fn main() {
-    let mut prev_vals: (u32, u32, u32, u32, f64) = (0, 0, 0, 0, 0.0);
-    let mut cur_vals: (u32, u32, u32, u32, f64);
-    let mut m = 100;
-    let mut n = 50;
-    let mut tree: Node = Node::Leaf;
+/// The formats a single tree can be written in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TreeFormat {
+    Csv,
+    Newick,
+    Dot,
+    Svg,
+    Archive,
+    #[cfg(feature = "serde")]
+    Json,
+}
+impl TreeFormat {
+    /// The format a path's extension names, CSV by default.
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("nwk" | "newick" | "tre") => TreeFormat::Newick,
+            Some("dot" | "gv") => TreeFormat::Dot,
+            Some("svg") => TreeFormat::Svg,
+            Some("ttb") => TreeFormat::Archive,
+            #[cfg(feature = "serde")]
+            Some("json") => TreeFormat::Json,
+            _ => TreeFormat::Csv,
+        }
+    }
+}
+/// The path typed at a prompt, `default` if blank, with `.csv` added if it
+/// has no extension.
+fn tree_path(input: &str, default: &str) -> PathBuf {
+    let path = Path::new(if input.is_empty() { default } else { input });
+    if path.extension().is_some() {
+        path.to_owned()
+    } else {
+        path.with_extension("csv")
+    }
+}
+/// Writes `tree` to `path`, in the format its extension names.
+fn write_tree(
+    path: &Path,
+    tree: &Generated,
+    origin: Option<&TreeGenerator>,
+    labels: bool,
+    lengths: bool,
+) -> std::io::Result<()> {
+    match TreeFormat::of(path) {
+        TreeFormat::Csv => csv::write_tree(path, tree.tree(), origin, tree.is_censored()),
+        TreeFormat::Newick => std::fs::write(
+            path,
+            newick::header(origin, tree.is_censored())
+                + &tree.tree().to_newick(labels, lengths)
+                + "\n",
+        ),
+        TreeFormat::Dot => std::fs::write(
+            path,
+            graphics::dot_header(origin, tree.is_censored()) + &tree.tree().to_dot(),
+        ),
+        TreeFormat::Svg => std::fs::write(
+            path,
+            graphics::svg_header(origin, tree.is_censored()) + &tree.tree().to_svg(),
+        ),
+        TreeFormat::Archive => {
+            let mut archive = archive::ArchiveWriter::create(path, origin)?;
+            archive.push(tree.tree(), tree.is_censored())?;
+            archive.finish().map(drop)
+        }
+        #[cfg(feature = "serde")]
+        TreeFormat::Json => json::write(
+            path,
+            &json::Document::Tree {
+                tree: tree.tree().clone(),
+                censored: tree.is_censored(),
+                origin: origin.cloned(),
+            },
+        ),
+    }
+}
+/// Reads tree `row` of the file at `path`, with the settings that generated
+/// it if known.
+fn read_tree(path: &Path, row: u32) -> Result<(Generated, Option<TreeGenerator>), String> {
+    match TreeFormat::of(path) {
+        TreeFormat::Csv => {}
+        TreeFormat::Newick => {
+            let newick = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            return Tree::from_newick(&newick).map_err(|e| e.to_string());
+        }
+        TreeFormat::Dot | TreeFormat::Svg => {
+            return Err("trees cannot be read back from pictures".into())
+        }
+        TreeFormat::Archive => {
+            let mut archive = archive::Archive::open(path).map_err(|e| e.to_string())?;
+            let tree = archive.tree(row).map_err(|e| e.to_string())?;
+            // Every tree of an archive was drawn from the header's settings,
+            // but only the first is the draw its seed makes.
+            let single = archive.len() == 1;
+            return Ok((tree, archive.origin.filter(|_| single)));
+        }
+        #[cfg(feature = "serde")]
+        TreeFormat::Json => {
+            let json::Document::Tree {
+                tree,
+                censored,
+                origin,
+            } = json::read(path).map_err(|e| e.to_string())?
+            else {
+                return Err("not a JSON file of a tree".into());
+            };
+            let tree = if censored {
+                Generated::Censored(tree)
+            } else {
+                Generated::Complete(tree)
+            };
+            return Ok((tree, origin));
+        }
+    }
+    let file = csv::TreeFile::read(path).map_err(|e| e.to_string())?;
+    let tree = file.tree(row).map_err(|e| e.to_string())?;
+    // A sample row is not the first draw from its file's seed.
+    Ok((tree, if file.is_samples() { None } else { file.origin }))
+}
+/// The settings in a JSON file, if it has them, with `sample_size` if it
+/// has none.
+#[cfg(feature = "serde")]
+fn read_settings(path: &Path, sample_size: u32) -> Result<Option<json::Settings>, json::JsonError> {
+    let settings = match json::read(path)? {
+        json::Document::Settings(settings) | json::Document::Stats { settings, .. } => {
+            Some(settings)
+        }
+        json::Document::Tree { origin, .. } => origin.map(|generator| json::Settings {
+            generator,
+            sample_size,
+        }),
+    };
+    if let Some(settings) = &settings {
+        settings
+            .generator
+            .validate()
+            .map_err(json::JsonError::Format)?;
+    }
+    Ok(settings)
+}
+fn generated_status(tree: &Generated, colour: bool) -> String {
+    if tree.is_censored() {
+        colour!(colour, "Tree censored at the size limits", 3)
+    } else {
+        colour!(colour, "Tree generated", 2)
+    }
+}
+/// Parses a percentile between 0 and 100.
+fn percentile(s: &str) -> Result<f64, &'static str> {
+    match s.trim().parse::<f64>() {
+        Ok(p) if (0.0..=100.0).contains(&p) => Ok(p),
+        _ => Err("percentiles must be numbers from 0 to 100"),
+    }
+}
+/// Asks a yes/no question, falling back to `default` on an empty or
+/// unreadable answer.
+fn confirm(out: &mut impl Write, prompt: &str, default: bool) -> bool {
+    write!(out, "{prompt} (y/n)[{}]: ", if default { "y" } else { "n" }).unwrap();
+    out.flush().unwrap();
+    let mut input = String::new();
+    match std::io::stdin().read_line(&mut input).map(|_| input.trim()) {
+        Ok("y" | "Y") => true,
+        Ok("n" | "N") => false,
+        _ => default,
+    }
+}
+fn percentile_list(percentiles: &[f64]) -> String {
+    percentiles
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+fn interactive(
+    mut generator: TreeGenerator,
+    mut sample_size: u32,
+    mut percentiles: Vec<f64>,
+    mut colour: bool,
+) {
+    let mut prev_stats = Stats::default();
+    // The last sample, with the settings that drew it.
+    let mut last_sample: Option<(Sample, TreeGenerator)> = None;
+    let mut last_prediction = Prediction::new(&generator.offspring);
+    let (mut bins, mut log_scale) = (20, false);
+    let mut tree = Generated::Complete(Tree::default());
     let mut status = String::new();
-    let mut sample_size = 1000;
-    let mut fast_rng = true;
-    let mut colour = true;
+    let mut tree_origin: Option<TreeGenerator> = None;
+    let mut size = Size::Nodes(100);
     let mut stdout_lock = stdout().lock();
     'main: loop {
         let mut input = String::new();
@@ -275,31 +743,54 @@ fn main() {
 	     {status}{}\
 	     Greetings!\n\
 	     Current settings are:\n\t\
-	     Branch P: {}({n}/{m});{}\n\
+	     Offspring law: {} (mean {:.3});{}\n\t\
+	     Seed: {}{}\n\t\
+	     Limits: {} nodes, {} generations\n\
 	     What would you like to do?\n\t\
 	     1. Change settings\n\t\
 	     2. Generate tree\n\t\
 	     3. Print tree\n\t\
-	     4. Collect stats from P\n\t\
-	     5. Write {} samples to file\n\t\
-	     6. Write current tree to file\n\t\
-	     7. Exit\n> ",
+	     4. Collect stats\n\t\
+	     5. Show histograms of the last stats\n\t\
+	     6. Show generation profile of the last stats\n\t\
+	     7. Write {} samples to file\n\t\
+	     8. Write current tree to file\n\t\
+	     9. Load tree from file\n\t\
+	     10. Exit\n\t\
+	     11. Generate tree of exact size\n> ",
             clear!(colour),
             if !status.is_empty() && !status.ends_with('\n') {
                 "\n"
             } else {
                 ""
             },
-            n as f64 / m as f64,
-            if n as f64 / m as f64 > 0.6 {
+            colour!(colour, generator.offspring, 4),
+            generator.offspring.mean(),
+            if generator.offspring.mean() >= 1.0 {
                 colour!(
                     colour,
-                    "\nWarning: high P may generate an infinite tree and crash.",
+                    "\nWarning: a mean of 1 or more may generate an infinite tree, \
+                     which will be censored.",
                     1
                 )
             } else {
                 String::new()
             },
+            colour!(colour, generator.seed, 4),
+            if generator.is_reproducible() {
+                String::new()
+            } else {
+                colour!(
+                    colour,
+                    format!(
+                        " (unused: the secure RNG reseeds from OS entropy every {} bytes)",
+                        generator.reseed
+                    ),
+                    3
+                )
+            },
+            colour!(colour, generator.max_nodes, 4),
+            colour!(colour, generator.max_generations, 4),
             colour!(colour, sample_size, 4)
         )
         .unwrap();
@@ -312,29 +803,31 @@ fn main() {
                         stdout_lock,
                         "{}\
 			 {status}{}\
-			 Branch P: {}\n\
 			 What would you like to change?\n\t\
-			 1. n({})\n\t\
-			 2. m({})\n\t\
-			 3. Change RNG strategy({})\n\t\
-			 4. Change sample size({})\n\t\
-			 5. Enable/disable colours\n\t\
-			 6. Back\n> ",
+			 1. Change offspring law({})\n\t\
+			 2. Change RNG strategy({})\n\t\
+			 3. Change sample size({})\n\t\
+			 4. Enable/disable colours\n\t\
+			 5. Change seed({})\n\t\
+			 6. Change max nodes({})\n\t\
+			 7. Change max generations({})\n\t\
+			 8. Change percentiles({})\n\t\
+			 9. Change secure RNG reseed threshold({} bytes, 0 = never)\n\t\
+			 10. Back\n> ",
                         clear!(colour),
                         if !status.is_empty() && !status.ends_with('\n') {
                             "\n"
                         } else {
                             ""
                         },
-                        n as f64 / m as f64,
-                        colour!(colour, n, 4),
-                        colour!(colour, m, 4),
-                        colour!(
-                            colour,
-                            if fast_rng { "Fast" } else { "Secure" },
-                            if fast_rng { 2 } else { 5 }
-                        ),
-                        colour!(colour, sample_size, 4)
+                        colour!(colour, generator.offspring, 4),
+                        colour!(colour, generator.rng, rng_tint(generator.rng)),
+                        colour!(colour, sample_size, 4),
+                        colour!(colour, generator.seed, 4),
+                        colour!(colour, generator.max_nodes, 4),
+                        colour!(colour, generator.max_generations, 4),
+                        colour!(colour, percentile_list(&percentiles), 4),
+                        colour!(colour, generator.reseed, 4)
                     )
                     .unwrap();
                     stdout_lock.flush().unwrap();
@@ -343,44 +836,27 @@ fn main() {
                     match std::io::stdin().read_line(&mut input) {
                         Ok(_) => match input.trim() {
                             "1" => {
-                                write!(stdout_lock, "Enter new n: ").unwrap();
+                                write!(
+                                    stdout_lock,
+                                    "Enter new offspring law (bernoulli-pair:n/m, binomial:p, \
+                                     geometric:p, poisson:λ or table:w0/w1/...): "
+                                )
+                                .unwrap();
                                 stdout_lock.flush().unwrap();
                                 let mut input = String::new();
                                 match std::io::stdin().read_line(&mut input) {
-                                    Ok(_) => match input.trim().parse::<u32>() {
+                                    Ok(_) => match input.trim().parse::<Offspring>() {
                                         Ok(val) => {
-                                            n = val;
-                                            status =
-                                                colour!(colour, format!("Changed n to {}", n), 2);
-                                        }
-                                        Err(e) => {
+                                            generator.offspring = val;
                                             status = colour!(
                                                 colour,
-                                                format!("Error parsing input: {}", e),
-                                                1
+                                                format!(
+                                                    "Changed offspring law to {}",
+                                                    generator.offspring
+                                                ),
+                                                2
                                             );
                                         }
-                                    },
-                                    Err(e) => {
-                                        status = colour!(
-                                            colour,
-                                            format!("Error reading input: {}", e),
-                                            1
-                                        );
-                                    }
-                                }
-                            }
-                            "2" => {
-                                write!(stdout_lock, "Enter new m: ").unwrap();
-                                stdout_lock.flush().unwrap();
-                                let mut input = String::new();
-                                match std::io::stdin().read_line(&mut input) {
-                                    Ok(_) => match input.trim().parse::<u32>() {
-                                        Ok(val) => {
-                                            m = val;
-                                            status =
-                                                colour!(colour, format!("Changed m to {}", m), 2);
-                                        }
                                         Err(e) => {
                                             status = colour!(
                                                 colour,
@@ -398,18 +874,17 @@ fn main() {
                                     }
                                 }
                             }
-                            "3" => {
-                                fast_rng = !fast_rng;
+                            "2" => {
+                                generator.rng = match generator.rng {
+                                    RngStrategy::Fast => RngStrategy::Secure,
+                                    RngStrategy::Secure => RngStrategy::Fast,
+                                };
                                 status = format!(
                                     "Changed RNG strategy to {}",
-                                    if fast_rng {
-                                        colour!(colour, "Fast", 2)
-                                    } else {
-                                        colour!(colour, "Secure", 5)
-                                    }
+                                    colour!(colour, generator.rng, rng_tint(generator.rng))
                                 );
                             }
-                            "4" => {
+                            "3" => {
                                 write!(stdout_lock, "Enter new sample size: ").unwrap();
                                 stdout_lock.flush().unwrap();
                                 let mut input = String::new();
@@ -448,7 +923,7 @@ fn main() {
                                     }
                                 }
                             }
-                            "5" => {
+                            "4" => {
                                 colour = !colour;
                                 status = colour!(
                                     colour,
@@ -459,7 +934,181 @@ fn main() {
                                     2
                                 );
                             }
+                            "5" => {
+                                write!(stdout_lock, "Enter new seed: ").unwrap();
+                                stdout_lock.flush().unwrap();
+                                let mut input = String::new();
+                                match std::io::stdin().read_line(&mut input) {
+                                    Ok(_) => match input.trim().parse::<u64>() {
+                                        Ok(val) => {
+                                            generator.seed = val;
+                                            status = colour!(
+                                                colour,
+                                                format!("Changed seed to {}", generator.seed),
+                                                2
+                                            );
+                                        }
+                                        Err(e) => {
+                                            status = colour!(
+                                                colour,
+                                                format!("Error parsing input: {}", e),
+                                                1
+                                            );
+                                        }
+                                    },
+                                    Err(e) => {
+                                        status = colour!(
+                                            colour,
+                                            format!("Error reading input: {}", e),
+                                            1
+                                        );
+                                    }
+                                }
+                            }
                             "6" => {
+                                write!(stdout_lock, "Enter new max nodes: ").unwrap();
+                                stdout_lock.flush().unwrap();
+                                let mut input = String::new();
+                                match std::io::stdin().read_line(&mut input) {
+                                    Ok(_) => match input.trim().parse::<u32>() {
+                                        Ok(val) => {
+                                            generator.max_nodes = val;
+                                            status = colour!(
+                                                colour,
+                                                format!(
+                                                    "Changed max nodes to {}",
+                                                    generator.max_nodes
+                                                ),
+                                                2
+                                            );
+                                        }
+                                        Err(e) => {
+                                            status = colour!(
+                                                colour,
+                                                format!("Error parsing input: {}", e),
+                                                1
+                                            );
+                                        }
+                                    },
+                                    Err(e) => {
+                                        status = colour!(
+                                            colour,
+                                            format!("Error reading input: {}", e),
+                                            1
+                                        );
+                                    }
+                                }
+                            }
+                            "7" => {
+                                write!(stdout_lock, "Enter new max generations: ").unwrap();
+                                stdout_lock.flush().unwrap();
+                                let mut input = String::new();
+                                match std::io::stdin().read_line(&mut input) {
+                                    Ok(_) => match input.trim().parse::<u32>() {
+                                        Ok(val) => {
+                                            generator.max_generations = val;
+                                            status = colour!(
+                                                colour,
+                                                format!(
+                                                    "Changed max generations to {}",
+                                                    generator.max_generations
+                                                ),
+                                                2
+                                            );
+                                        }
+                                        Err(e) => {
+                                            status = colour!(
+                                                colour,
+                                                format!("Error parsing input: {}", e),
+                                                1
+                                            );
+                                        }
+                                    },
+                                    Err(e) => {
+                                        status = colour!(
+                                            colour,
+                                            format!("Error reading input: {}", e),
+                                            1
+                                        );
+                                    }
+                                }
+                            }
+                            "8" => {
+                                write!(stdout_lock, "Enter new percentiles, separated by commas: ")
+                                    .unwrap();
+                                stdout_lock.flush().unwrap();
+                                let mut input = String::new();
+                                match std::io::stdin().read_line(&mut input) {
+                                    Ok(_) => match input
+                                        .split(',')
+                                        .filter(|p| !p.trim().is_empty())
+                                        .map(percentile)
+                                        .collect::<Result<Vec<_>, _>>()
+                                    {
+                                        Ok(val) => {
+                                            percentiles = val;
+                                            status = colour!(
+                                                colour,
+                                                format!(
+                                                    "Changed percentiles to {}",
+                                                    percentile_list(&percentiles)
+                                                ),
+                                                2
+                                            );
+                                        }
+                                        Err(e) => {
+                                            status = colour!(
+                                                colour,
+                                                format!("Error parsing input: {}", e),
+                                                1
+                                            );
+                                        }
+                                    },
+                                    Err(e) => {
+                                        status = colour!(
+                                            colour,
+                                            format!("Error reading input: {}", e),
+                                            1
+                                        );
+                                    }
+                                }
+                            }
+                            "9" => {
+                                write!(stdout_lock, "Enter new reseed threshold in bytes: ")
+                                    .unwrap();
+                                stdout_lock.flush().unwrap();
+                                let mut input = String::new();
+                                match std::io::stdin().read_line(&mut input) {
+                                    Ok(_) => match input.trim().parse::<u64>() {
+                                        Ok(val) => {
+                                            generator.reseed = val;
+                                            status = colour!(
+                                                colour,
+                                                format!(
+                                                    "Changed reseed threshold to {}",
+                                                    generator.reseed
+                                                ),
+                                                2
+                                            );
+                                        }
+                                        Err(e) => {
+                                            status = colour!(
+                                                colour,
+                                                format!("Error parsing input: {}", e),
+                                                1
+                                            );
+                                        }
+                                    },
+                                    Err(e) => {
+                                        status = colour!(
+                                            colour,
+                                            format!("Error reading input: {}", e),
+                                            1
+                                        );
+                                    }
+                                }
+                            }
+                            "10" => {
                                 break 'settings;
                             }
                             _ => {
@@ -472,96 +1121,153 @@ fn main() {
                     }
                 },
                 "2" => {
-                    tree = generate_tree(true, n, m, fast_rng);
+                    tree = generator.generate();
+                    tree_origin = Some(generator.clone());
                     status = format!(
-                        "{}\n{}",
-                        colour!(colour, "Tree generated", 2),
-                        get_tree_stats(&tree)
+                        "{} (seed {})\n{}",
+                        generated_status(&tree, colour),
+                        colour!(colour, generator.seed, 4),
+                        get_tree_stats(tree.tree())
                     );
+                    generator.seed = generator.seed.wrapping_add(1);
                 }
                 "3" => {
                     status = format!(
                         "{}{}{}",
-                        print_tree(&tree, colour, Vec::new()),
-                        get_tree_rolls(&tree, count_generations(&tree), colour),
-                        get_tree_stats(&tree)
+                        print_tree(tree.tree()),
+                        get_tree_rolls(tree.tree(), colour),
+                        get_tree_stats(tree.tree())
                     );
                 }
                 "4" => {
-                    cur_vals = check_stats(n, m, fast_rng, sample_size);
+                    last_prediction = Prediction::new(&generator.offspring);
+                    let sample = Sample::draw(&generator, sample_size);
+                    let stats = sample.stats(&percentiles);
                     status = format!(
-                        "Generated {} samples:\n{}",
+                        "Generated {} samples (seed {}), {} censored:\n{}\n{}",
                         colour!(colour, sample_size, 4),
-                        print_stats_delta(prev_vals, cur_vals, colour)
+                        colour!(colour, generator.seed, 4),
+                        colour!(
+                            colour,
+                            stats.censored,
+                            if stats.censored > 0 { 3 } else { 4 }
+                        ),
+                        print_stats_delta(&prev_stats, &stats, colour),
+                        print_prediction(&last_prediction, &stats, colour)
                     );
-                    prev_vals = cur_vals;
+                    prev_stats = stats;
+                    last_sample = Some((sample, generator.clone()));
+                    generator.seed = generator.seed.wrapping_add(1);
+                }
+                "5" => 'histograms: {
+                    let Some((sample, origin)) = &last_sample else {
+                        status = colour!(colour, "Collect stats first", 1);
+                        break 'histograms;
+                    };
+                    let mut input = String::new();
+                    write!(stdout_lock, "Enter number of bins[{bins}]: ").unwrap();
+                    stdout_lock.flush().unwrap();
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        break 'histograms;
+                    }
+                    if !input.trim().is_empty() {
+                        match input.trim().parse::<u32>() {
+                            Ok(val) if val > 0 => bins = val,
+                            _ => {
+                                status = colour!(
+                                    colour,
+                                    "Error parsing input: bins must be a positive number",
+                                    1
+                                );
+                                break 'histograms;
+                            }
+                        }
+                    }
+                    log_scale = confirm(&mut stdout_lock, "Log scale?", log_scale);
+                    let histograms = sample.histograms(bins);
+                    status = histograms
+                        .iter()
+                        .map(|(name, histogram)| {
+                            print_histogram(name, histogram, log_scale, colour)
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let mut input = String::new();
+                    write!(
+                        stdout_lock,
+                        "Enter filename without extension to write the bins, \
+                         or nothing to skip: "
+                    )
+                    .unwrap();
+                    stdout_lock.flush().unwrap();
+                    match std::io::stdin().read_line(&mut input) {
+                        Ok(_) if input.trim().is_empty() => {}
+                        Ok(_) => {
+                            let filename = format!("{}.csv", input.trim());
+                            status.push_str(&match csv::write_histograms(
+                                &filename,
+                                &histograms,
+                                origin,
+                                sample.samples,
+                            ) {
+                                Ok(()) => format!(
+                                    "\nWrote histograms to file {}",
+                                    colour!(colour, filename, 4)
+                                ),
+                                Err(e) => {
+                                    colour!(colour, format!("\nError writing file: {}", e), 1)
+                                }
+                            });
+                        }
+                        Err(e) => {
+                            status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        }
+                    }
                 }
-                "5" => {
-                    let default_filename = format!("{n}-{m}-x{sample_size}");
+                "6" => 'profile: {
+                    let Some((sample, origin)) = &last_sample else {
+                        status = colour!(colour, "Collect stats first", 1);
+                        break 'profile;
+                    };
+                    status = print_profile(&prev_stats.profile, &last_prediction, colour);
                     let mut input = String::new();
                     write!(
                         stdout_lock,
-                        "Enter filename without extension[{default_filename}]: "
+                        "{status}\nEnter filename without extension to write the profile, \
+                         or nothing to skip: "
                     )
                     .unwrap();
                     stdout_lock.flush().unwrap();
                     match std::io::stdin().read_line(&mut input) {
-                        Ok(_) => match std::fs::write(
-                            format!(
-                                "{}.csv",
-                                if input.trim().is_empty() {
-                                    &default_filename
-                                } else {
-                                    input.trim()
+                        Ok(_) if input.trim().is_empty() => {}
+                        Ok(_) => {
+                            let filename = format!("{}.csv", input.trim());
+                            status.push_str(&match csv::write_profile(
+                                &filename,
+                                &prev_stats.profile,
+                                &last_prediction,
+                                origin,
+                                sample.samples,
+                            ) {
+                                Ok(()) => format!(
+                                    "\nWrote profile to file {}",
+                                    colour!(colour, filename, 4)
+                                ),
+                                Err(e) => {
+                                    colour!(colour, format!("\nError writing file: {}", e), 1)
                                 }
-                            ),
-                            format!(
-                                "0,leaves,branches,nodes,generations,rolls\n{}",
-                                (1..=sample_size).fold(String::new(), |mut acc, i| {
-                                    let t = generate_tree(true, n, m, fast_rng);
-                                    acc.push_str(&format!(
-                                        "{i},{},{},{},{},{}\n",
-                                        count_leaves(&t),
-                                        count_branches(&t),
-                                        count_nodes(&t),
-                                        count_generations(&t),
-                                        tree_to_string(&t, false)
-                                    ));
-                                    acc
-                                })
-                            ),
-                        ) {
-                            Ok(_) => {
-                                status = format!(
-                                    "Wrote {} samples to file {}",
-                                    colour!(colour, sample_size, 4),
-                                    colour!(
-                                        colour,
-                                        if input.trim().is_empty() {
-                                            &default_filename
-                                        } else {
-                                            input.trim()
-                                        },
-                                        4
-                                    )
-                                );
-                            }
-                            Err(e) => {
-                                status = colour!(colour, format!("Error writing file: {}", e), 1);
-                            }
-                        },
+                            });
+                        }
                         Err(e) => {
                             status = colour!(colour, format!("Error reading input: {}", e), 1);
                         }
                     }
                 }
-                "6" => {
+                "7" => {
                     let default_filename = format!(
-                        "{}-{}-{}-{}",
-                        count_leaves(&tree),
-                        count_branches(&tree),
-                        count_nodes(&tree),
-                        count_generations(&tree)
+                        "{}-x{sample_size}",
+                        generator.offspring.to_string().replace([':', '/'], "-")
                     );
                     let mut input = String::new();
                     write!(
@@ -570,51 +1276,193 @@ fn main() {
                     )
                     .unwrap();
                     stdout_lock.flush().unwrap();
-                    match std::io::stdin().read_line(&mut input) {
-                        Ok(_) => match std::fs::write(
-                            format!(
-                                "{}.csv",
-                                if input.trim().is_empty() {
-                                    &default_filename
-                                } else {
-                                    input.trim()
-                                }
-                            ),
-                            format!(
-                                "{},{},{},{},{}\n",
-                                count_leaves(&tree),
-                                count_branches(&tree),
-                                count_nodes(&tree),
-                                count_generations(&tree),
-                                tree_to_string(&tree, false),
-                            ),
-                        ) {
-                            Ok(_) => {
-                                status = format!(
-                                    "Wrote current tree to file {}",
-                                    colour!(
-                                        colour,
-                                        if input.trim().is_empty() {
-                                            &default_filename
-                                        } else {
-                                            input.trim()
-                                        },
-                                        4
-                                    )
-                                );
-                            }
-                            Err(e) => {
-                                status = colour!(colour, format!("Error writing file: {}", e), 1);
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        continue;
+                    }
+                    let path = PathBuf::from(format!(
+                        "{}.csv",
+                        if input.trim().is_empty() {
+                            &default_filename
+                        } else {
+                            input.trim()
+                        }
+                    ));
+                    let resume = path.exists()
+                        && confirm(
+                            &mut stdout_lock,
+                            &format!("File exists. Resume it up to {sample_size} samples?"),
+                            true,
+                        );
+                    let origin = (!resume).then_some(&generator);
+                    let rolls = resume
+                        || confirm(&mut stdout_lock, "Include the rolls of each tree?", true);
+                    match export_samples(
+                        &mut stdout_lock,
+                        true,
+                        &path,
+                        origin,
+                        sample_size,
+                        rolls,
+                        colour,
+                    ) {
+                        Ok(progress) => {
+                            status = sample_status(&progress, &path.display(), colour);
+                            if !resume {
+                                generator.seed = generator.seed.wrapping_add(1);
                             }
-                        },
+                        }
                         Err(e) => {
-                            status = colour!(colour, format!("Error reading input: {}", e), 1);
+                            status = colour!(colour, format!("Error writing file: {}", e), 1);
                         }
                     }
                 }
-                "7" => {
+                "8" => {
+                    let default_filename = {
+                        let counts = tree.tree().counts();
+                        format!(
+                            "{}-{}-{}-{}.csv",
+                            counts.leaves, counts.branches, counts.nodes, counts.generations
+                        )
+                    };
+                    let mut input = String::new();
+                    write!(
+                        stdout_lock,
+                        "Enter filename; .nwk, .ttb, .dot and .svg pick other formats[{default_filename}]: "
+                    )
+                    .unwrap();
+                    stdout_lock.flush().unwrap();
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        continue;
+                    }
+                    let path = tree_path(input.trim(), &default_filename);
+                    let (labels, lengths) = if TreeFormat::of(&path) == TreeFormat::Newick {
+                        (
+                            confirm(&mut stdout_lock, "Label the nodes?", false),
+                            confirm(&mut stdout_lock, "Include branch lengths?", false),
+                        )
+                    } else {
+                        (false, false)
+                    };
+                    match write_tree(&path, &tree, tree_origin.as_ref(), labels, lengths) {
+                        Ok(()) => {
+                            status = format!(
+                                "Wrote current tree to file {}",
+                                colour!(colour, path.display(), 4)
+                            );
+                        }
+                        Err(e) => {
+                            status = colour!(colour, format!("Error writing file: {}", e), 1);
+                        }
+                    }
+                }
+                "9" => {
+                    let mut input = String::new();
+                    write!(
+                        stdout_lock,
+                        "Enter filename, .nwk for Newick or .ttb for an archive[.csv]: "
+                    )
+                    .unwrap();
+                    stdout_lock.flush().unwrap();
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        continue;
+                    }
+                    let path = tree_path(input.trim(), "");
+                    let samples = match TreeFormat::of(&path) {
+                        TreeFormat::Csv => csv::TreeFile::read(&path)
+                            .map(|file| if file.is_samples() { file.len() } else { 0 })
+                            .map_err(|e| format!("Error loading tree: {}", e)),
+                        TreeFormat::Archive => archive::Archive::open(&path)
+                            .map(|archive| if archive.len() > 1 { archive.len() } else { 0 })
+                            .map_err(|e| format!("Error loading tree: {}", e)),
+                        _ => Ok(0),
+                    };
+                    let row = samples.and_then(|samples| {
+                        if samples == 0 {
+                            return Ok(1);
+                        }
+                        write!(stdout_lock, "Enter sample index[1-{samples}]: ").unwrap();
+                        stdout_lock.flush().unwrap();
+                        let mut input = String::new();
+                        match std::io::stdin().read_line(&mut input) {
+                            Ok(_) if input.trim().is_empty() => Ok(1),
+                            Ok(_) => input
+                                .trim()
+                                .parse::<u32>()
+                                .map_err(|e| format!("Error parsing input: {}", e)),
+                            Err(e) => Err(format!("Error reading input: {}", e)),
+                        }
+                    });
+                    match row.and_then(|row| {
+                        read_tree(&path, row).map_err(|e| format!("Error loading tree: {}", e))
+                    }) {
+                        Ok((loaded, origin)) => {
+                            tree = loaded;
+                            tree_origin = origin;
+                            status = format!(
+                                "{} from file {}\n{}",
+                                colour!(colour, "Tree loaded", 2),
+                                colour!(colour, path.display(), 4),
+                                get_tree_stats(tree.tree())
+                            );
+                        }
+                        Err(e) => {
+                            status = colour!(colour, e, 1);
+                        }
+                    }
+                }
+                "10" => {
                     break 'main;
                 }
+                "11" => {
+                    let mut input = String::new();
+                    write!(
+                        stdout_lock,
+                        "Enter a number of nodes, or of leaves followed by L[{}]: ",
+                        size
+                    )
+                    .unwrap();
+                    stdout_lock.flush().unwrap();
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        status = colour!(colour, format!("Error reading input: {}", e), 1);
+                        continue;
+                    }
+                    let input = input.trim();
+                    if !input.is_empty() {
+                        let (count, leaves) = match input.strip_suffix(['l', 'L']) {
+                            Some(count) => (count.trim(), true),
+                            None => (input, false),
+                        };
+                        match count.parse::<u32>() {
+                            Ok(count) if leaves => size = Size::Leaves(count),
+                            Ok(count) => size = Size::Nodes(count),
+                            Err(e) => {
+                                status = colour!(colour, format!("Error parsing input: {}", e), 1);
+                                continue;
+                            }
+                        }
+                    }
+                    match generator.generate_sized(size) {
+                        Ok(sized) => {
+                            tree = Generated::Complete(sized);
+                            // The seed alone does not give this tree back.
+                            tree_origin = None;
+                            status = format!(
+                                "{} (seed {}, {})\n{}",
+                                generated_status(&tree, colour),
+                                colour!(colour, generator.seed, 4),
+                                size,
+                                get_tree_stats(tree.tree())
+                            );
+                            generator.seed = generator.seed.wrapping_add(1);
+                        }
+                        Err(e) => {
+                            status = colour!(colour, format!("Error: {}", e), 1);
+                        }
+                    }
+                }
                 _ => {
                     status = colour!(colour, "Invalid input", 1);
                 }
@@ -625,114 +1473,3 @@ fn main() {
         }
     }
 }
-fn print_tree(tree: &Node, last: bool, mut branches: Vec<u32>) -> String {
-    let generations = *branches.last().unwrap_or(&0);
-    let mut res = String::new();
-    if last {
-        branches.pop();
-    }
-    for i in 1..generations {
-        res.push_str(if branches.contains(&i) { "║" } else { " " });
-    }
-    match tree {
-        Node::Leaf => {
-            res.push_str(if last { "╚Leaf\n" } else { "╠Leaf\n" });
-            res
-        }
-
-        Node::Branch(bx) => {
-            res.push_str(if generations == 0 {
-                "Root\n"
-            } else if last {
-                "╚Branch\n"
-            } else {
-                "╠Branch\n"
-            });
-            let (mut new_branches1, mut new_branches2) = (branches.clone(), branches.clone());
-            new_branches1.push(generations + 1);
-            res.push_str(&print_tree(&bx.0, false, new_branches1));
-            new_branches2.push(generations + 1);
-            res.push_str(&print_tree(&bx.1, true, new_branches2));
-            res
-        }
-    }
-}
-
-fn generate_tree(root: bool, n: u32, m: u32, fast_rng: bool) -> Node {
-    let branch_chance = if fast_rng {
-        |n, m| rand::thread_rng().gen_range(0..m) < n //Fast RNG
-    } else {
-        |n, m| ReseedingRng::new(ChaCha20Core::from_entropy(), 4, OsRng).gen_range(0..m) < n
-        // CSRNG
-    };
-    if root {
-        Node::Branch(Box::from((
-            Node::Branch(Box::from((
-                generate_tree(false, n, m, fast_rng),
-                generate_tree(false, n, m, fast_rng),
-            ))),
-            Node::Branch(Box::from((
-                generate_tree(false, n, m, fast_rng),
-                generate_tree(false, n, m, fast_rng),
-            ))),
-        )))
-    } else if branch_chance(m - n, m) {
-        Node::Leaf
-    } else {
-        Node::Branch(Box::from((
-            generate_tree(false, n, m, fast_rng),
-            if branch_chance(n, m) {
-                generate_tree(false, n, m, fast_rng)
-            } else {
-                Node::Leaf
-            },
-        )))
-    }
-}
-fn count_leaves(tree: &Node) -> u32 {
-    match tree {
-        Node::Leaf => 1,
-        Node::Branch(bx) => count_leaves(&bx.0) + count_leaves(&bx.1),
-    }
-}
-fn count_branches(tree: &Node) -> u32 {
-    match tree {
-        Node::Leaf => 0,
-        Node::Branch(bx) => 1 + count_branches(&bx.0) + count_branches(&bx.1),
-    }
-}
-fn count_nodes(tree: &Node) -> u32 {
-    match tree {
-        Node::Leaf => 1,
-        Node::Branch(bx) => 1 + count_nodes(&bx.0) + count_nodes(&bx.1),
-    }
-}
-fn count_generations(tree: &Node) -> u32 {
-    match tree {
-        Node::Leaf => 0,
-        Node::Branch(bx) => 1 + count_generations(&bx.0).max(count_generations(&bx.1)),
-    }
-}
-fn get_nodes_at_generation(tree: &Node, seek_gen: u32, gen: u32, colour: bool) -> String {
-    if gen == seek_gen {
-        match tree {
-            Node::Leaf => colour!(colour, "0", gen % 8),
-            Node::Branch(_) => colour!(colour, "1", gen % 8),
-        }
-    } else {
-        match tree {
-            Node::Leaf => String::from(""),
-            Node::Branch(bx) => {
-                get_nodes_at_generation(&bx.0, seek_gen, gen + 1, colour)
-                    + &get_nodes_at_generation(&bx.1, seek_gen, gen + 1, colour)
-            }
-        }
-    }
-}
-
-fn tree_to_string(tree: &Node, colour: bool) -> String {
-    (2..=count_generations(tree)).fold(String::new(), |mut acc, i| {
-        acc.push_str(&get_nodes_at_generation(tree, i, 0, colour));
-        acc
-    })
-}
@@ -0,0 +1,114 @@
+//! Every per-tree metric, from one pass over the tree.
+use crate::{
+    profile::Profile,
+    shape::Shape,
+    tree::{Counts, Tree},
+};
+
+/// The sizes, [`Shape`], [`Profile`] and rolls of one tree.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TreeMetrics {
+    pub counts: Counts,
+    pub shape: Shape,
+    pub profile: Profile,
+    /// The tree's [`rolls`](Tree::rolls), empty if measured
+    /// [`without_rolls`](TreeMetrics::without_rolls).
+    pub rolls: String,
+}
+impl TreeMetrics {
+    /// Measures `tree` in a single pass, from its last node back to the root,
+    /// so that every node is seen after its children.
+    pub fn new(tree: &Tree) -> Self {
+        Self::measure(tree, true)
+    }
+    /// Measures `tree` like [`new`](TreeMetrics::new), but leaves the rolls
+    /// empty, for callers that only want the numbers.
+    pub fn without_rolls(tree: &Tree) -> Self {
+        Self::measure(tree, false)
+    }
+    fn measure(tree: &Tree, with_rolls: bool) -> Self {
+        let nodes = tree.nodes() as usize;
+        let generations = tree.generations();
+        let mut profile = Profile {
+            leaves: vec![0; generations as usize + 1],
+            branches: vec![0; generations as usize + 1],
+        };
+        let mut shape = Shape::default();
+        // Leaves below, and Horton–Strahler order, of each node.
+        let mut leaves = vec![0u64; nodes];
+        let mut order = vec![0u32; nodes];
+        let mut below = Vec::new();
+        // The rolls skip the two fixed generations, and are written backwards.
+        let first_roll = if with_rolls {
+            tree.generation(2).start
+        } else {
+            u32::MAX
+        };
+        let mut rolls = String::with_capacity(nodes.saturating_sub(first_roll as usize));
+        for gen in (0..=generations).rev() {
+            for node in tree.generation(gen).rev() {
+                let children = tree.children(node);
+                if node >= first_roll {
+                    push_roll_backwards(&mut rolls, children.len() as u32);
+                }
+                shape.path_length += gen as u64;
+                if children.is_empty() {
+                    profile.leaves[gen as usize] += 1;
+                    shape.sackin += gen as u64;
+                    (leaves[node as usize], order[node as usize]) = (1, 1);
+                    continue;
+                }
+                profile.branches[gen as usize] += 1;
+                below.clear();
+                below.extend(children.clone().map(|child| leaves[child as usize]));
+                below.sort_unstable();
+                // Each leaf count is above those before it, and below those
+                // after it.
+                let k = below.len() as u64;
+                shape.colless += below
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &n)| n * (2 * i as u64 + 1))
+                    .sum::<u64>()
+                    - below.iter().map(|&n| n * k).sum::<u64>();
+                shape.cherries += (children.len() == 2
+                    && children.clone().all(|child| tree.child_count(child) == 0))
+                    as u32;
+                leaves[node as usize] = below.iter().sum();
+                let top = children.clone().map(|c| order[c as usize]).max().unwrap();
+                let ties = children.filter(|&c| order[c as usize] == top).count();
+                order[node as usize] = top + (ties > 1) as u32;
+            }
+        }
+        // Reversing the bytes puts the rolls, and the digits of `[k]`, back
+        // in order.
+        let mut rolls = rolls.into_bytes();
+        rolls.reverse();
+        let rolls = String::from_utf8(rolls).expect("rolls are ASCII");
+        shape.strahler = order[0];
+        shape.widths = profile.widths().collect();
+        let leaves = profile.leaves.iter().map(|&l| l as u64).sum();
+        TreeMetrics {
+            counts: Counts {
+                leaves,
+                branches: nodes as u64 - leaves,
+                nodes: nodes as u64,
+                generations: generations as u64,
+            },
+            shape,
+            profile,
+            rolls,
+        }
+    }
+}
+
+/// Appends the roll of a node with `count` children, back to front.
+fn push_roll_backwards(rolls: &mut String, count: u32) {
+    if count < 10 {
+        rolls.push(char::from(b'0' + count as u8));
+    } else {
+        rolls.push(']');
+        rolls.extend(count.to_string().chars().rev());
+        rolls.push('[');
+    }
+}
@@ -0,0 +1,249 @@
+//! Newick export and import.
+//!
+//! Newick nests each node's children in parentheses, so a tree reads
+//! `((,),(,));`. Trees here carry no names or distances, so exported labels
+//! are level-order node indices and every branch is one generation long;
+//! labels and lengths on import are checked and then dropped. A leading
+//! [`header`] comment keeps the settings that generated the tree.
+use crate::{
+    csv,
+    generator::{Generated, TreeGenerator},
+    tree::{Node, Tree},
+};
+
+/// The `[# version=..,law=..,seed=..]` comment that opens a Newick file,
+/// with the [`csv::header`] fields of the tree's `origin`.
+pub fn header(origin: Option<&TreeGenerator>, censored: bool) -> String {
+    format!("[{}]\n", csv::header(origin, censored).trim_end())
+}
+
+impl Tree {
+    /// The tree in Newick format, ending with `;`.
+    ///
+    /// With `labels`, every node is named after its level-order index, and
+    /// with `lengths`, every branch gets length 1.
+    pub fn to_newick(&self, labels: bool, lengths: bool) -> String {
+        enum Step {
+            Open(u32),
+            Close(u32),
+            Comma,
+        }
+        let mut res = String::new();
+        let mut stack = vec![Step::Open(0)];
+        while let Some(step) = stack.pop() {
+            let node = match step {
+                Step::Comma => {
+                    res.push(',');
+                    continue;
+                }
+                Step::Open(node) if self.child_count(node) > 0 => {
+                    res.push('(');
+                    stack.push(Step::Close(node));
+                    for child in self.children(node).rev() {
+                        stack.push(Step::Open(child));
+                        stack.push(Step::Comma);
+                    }
+                    // No comma before the first child.
+                    stack.pop();
+                    continue;
+                }
+                Step::Close(node) => {
+                    res.push(')');
+                    node
+                }
+                Step::Open(node) => node,
+            };
+            if labels {
+                res.push_str(&format!("n{node}"));
+            }
+            if lengths && node > 0 {
+                res.push_str(":1");
+            }
+        }
+        res.push(';');
+        res
+    }
+    /// Reads a tree in Newick format, with any number of children per node,
+    /// and the settings that generated it if a leading [`header`] gives them.
+    ///
+    /// Labels, branch lengths and other `[...]` comments are allowed but
+    /// dropped.
+    pub fn from_newick(newick: &str) -> Result<(Generated, Option<TreeGenerator>), NewickError> {
+        let start = newick.len() - newick.trim_start().len();
+        let (mut origin, mut censored, mut pos) = (None, false, start);
+        if let Some(comment) = newick[start..].strip_prefix("[#") {
+            let end = comment
+                .find(']')
+                .ok_or(NewickError::Unterminated { position: start })?;
+            let (_, generator, flag) =
+                csv::parse_header(&comment[..end]).map_err(NewickError::Header)?;
+            (origin, censored, pos) = (generator, flag, start + "[#".len() + end + 1);
+        }
+        let tree = Tree::from(parse(newick, pos)?);
+        Ok((Generated::new(tree, censored), origin))
+    }
+}
+
+/// Parses `newick` from byte `pos` into boxed nodes, without recursing.
+fn parse(newick: &str, pos: usize) -> Result<Node, NewickError> {
+    let mut chars = Chars { src: newick, pos };
+    // The children read so far of each open parenthesis.
+    let mut open: Vec<Vec<Node>> = Vec::new();
+    loop {
+        // Start of a subtree.
+        chars.skip_blank()?;
+        let mut node = if chars.peek() == Some('(') {
+            chars.next();
+            open.push(Vec::new());
+            continue;
+        } else {
+            Node::Leaf
+        };
+        // End of a subtree, and of every list it closes.
+        loop {
+            chars.label()?;
+            chars.length()?;
+            let Some(siblings) = open.last_mut() else {
+                return match chars.next() {
+                    Some((_, ';')) => match chars.skip_blank()?.next() {
+                        None => Ok(node),
+                        Some((position, _)) => Err(NewickError::Trailing { position }),
+                    },
+                    Some((position, found)) => Err(NewickError::Unexpected { position, found }),
+                    None => Err(NewickError::MissingSemicolon),
+                };
+            };
+            siblings.push(node);
+            match chars.skip_blank()?.next() {
+                Some((_, ',')) => break,
+                Some((_, ')')) => node = Node::Branch(open.pop().unwrap()),
+                Some((position, found)) => return Err(NewickError::Unexpected { position, found }),
+                None => return Err(NewickError::Unclosed { depth: open.len() }),
+            }
+        }
+    }
+}
+
+/// A cursor over Newick text.
+struct Chars<'a> {
+    src: &'a str,
+    pos: usize,
+}
+impl Chars<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+    fn next(&mut self) -> Option<(usize, char)> {
+        let c = self.peek()?;
+        let position = self.pos;
+        self.pos += c.len_utf8();
+        Some((position, c))
+    }
+    /// Skips whitespace and comments.
+    fn skip_blank(&mut self) -> Result<&mut Self, NewickError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next();
+                }
+                Some('[') => {
+                    let position = self.pos;
+                    let end = self.src[position..]
+                        .find(']')
+                        .ok_or(NewickError::Unterminated { position })?;
+                    self.pos += end + 1;
+                }
+                _ => return Ok(self),
+            }
+        }
+    }
+    /// Skips a quoted or unquoted label, if any.
+    fn label(&mut self) -> Result<(), NewickError> {
+        self.skip_blank()?;
+        if self.peek() == Some('\'') {
+            let position = self.pos;
+            self.next();
+            // A doubled quote stands for one inside the label.
+            loop {
+                match self.next() {
+                    Some((_, '\'')) if self.peek() == Some('\'') => {
+                        self.next();
+                    }
+                    Some((_, '\'')) => break,
+                    Some(_) => {}
+                    None => return Err(NewickError::Unterminated { position }),
+                }
+            }
+        } else {
+            while self
+                .peek()
+                .is_some_and(|c| !c.is_whitespace() && !"()[]':;,".contains(c))
+            {
+                self.next();
+            }
+        }
+        Ok(())
+    }
+    /// Skips a `:length`, if any, checking that it is a number.
+    fn length(&mut self) -> Result<(), NewickError> {
+        if self.skip_blank()?.peek() != Some(':') {
+            return Ok(());
+        }
+        self.next();
+        self.skip_blank()?;
+        let position = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            self.next();
+        }
+        match self.src[position..self.pos].parse::<f64>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(NewickError::Length { position }),
+        }
+    }
+}
+
+/// Why a Newick string could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewickError {
+    /// A character out of place, at byte `position`.
+    Unexpected { position: usize, found: char },
+    /// The text ends with `depth` parentheses still open.
+    Unclosed { depth: usize },
+    /// The tree is not followed by `;`.
+    MissingSemicolon,
+    /// Text after the closing `;`, from byte `position`.
+    Trailing { position: usize },
+    /// A quoted label or comment from byte `position` is never closed.
+    Unterminated { position: usize },
+    /// The branch length at byte `position` is not a number.
+    Length { position: usize },
+    /// The leading settings comment is malformed.
+    Header(&'static str),
+}
+impl std::fmt::Display for NewickError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NewickError::Unexpected { position, found } => {
+                write!(f, "unexpected {found:?} at position {position}")
+            }
+            NewickError::Unclosed { depth } => {
+                write!(f, "text ends with {depth} parentheses still open")
+            }
+            NewickError::MissingSemicolon => write!(f, "tree does not end with ';'"),
+            NewickError::Trailing { position } => {
+                write!(f, "unexpected text from position {position} after ';'")
+            }
+            NewickError::Unterminated { position } => {
+                write!(f, "quote or comment at position {position} is never closed")
+            }
+            NewickError::Length { position } => {
+                write!(f, "invalid branch length at position {position}")
+            }
+            NewickError::Header(e) => write!(f, "malformed settings comment: {e}"),
+        }
+    }
+}
+impl std::error::Error for NewickError {}
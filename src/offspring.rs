@@ -0,0 +1,278 @@
+//! Offspring distributions for the branching process.
+use rand::prelude::*;
+
+/// The law of the number of children of each node past generation 1.
+pub trait OffspringDistribution {
+    /// Draws a number of children.
+    fn sample(&self, rng: &mut dyn RngCore) -> u32;
+    /// Probability of exactly `k` children.
+    fn probability(&self, k: u32) -> f64;
+    /// Expected number of children.
+    fn mean(&self) -> f64 {
+        terms(self).map(|(k, p)| k as f64 * p).sum()
+    }
+    /// Variance of the number of children.
+    fn variance(&self) -> f64 {
+        let mean = self.mean();
+        terms(self)
+            .map(|(k, p)| (k as f64 - mean).powi(2) * p)
+            .sum()
+    }
+}
+
+/// The pairs `(k, P(k))`, until all but a negligible mass is covered.
+pub(crate) fn terms(
+    law: &(impl OffspringDistribution + ?Sized),
+) -> impl Iterator<Item = (u32, f64)> + '_ {
+    (0..10_000).scan(0.0, move |mass, k| {
+        if *mass >= 1.0 - 1e-12 {
+            return None;
+        }
+        let p = law.probability(k);
+        *mass += p;
+        Some((k, p))
+    })
+}
+
+/// The built-in offspring laws.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Offspring {
+    /// No children with probability `(m - n) / m`; otherwise a first child,
+    /// and a second one with probability `n / m`.
+    BernoulliPair { n: u32, m: u32 },
+    /// Binomial(2, p) children.
+    Binomial { p: f64 },
+    /// `k` children with probability `(1 - p)^k p`.
+    Geometric { p: f64 },
+    /// Poisson(λ) children.
+    Poisson { lambda: f64 },
+    /// `k` children with probability proportional to the `k`th weight.
+    Table(Vec<f64>),
+}
+impl Offspring {
+    /// Checks the law's parameters.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let probability = |p: f64| (0.0..=1.0).contains(&p);
+        match self {
+            Offspring::BernoulliPair { m: 0, .. } => Err("m must be positive"),
+            Offspring::BernoulliPair { n, m } if n > m => Err("n must not exceed m"),
+            Offspring::Binomial { p } if !probability(*p) => Err("p must be in [0, 1]"),
+            Offspring::Geometric { p } if !probability(*p) || *p == 0.0 => {
+                Err("p must be in (0, 1]")
+            }
+            Offspring::Poisson { lambda } if !(lambda.is_finite() && *lambda >= 0.0) => {
+                Err("λ must be non-negative")
+            }
+            Offspring::Table(weights)
+                if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0))
+                    || weights.iter().sum::<f64>() <= 0.0 =>
+            {
+                Err("table weights must be non-negative with a positive sum")
+            }
+            _ => Ok(()),
+        }
+    }
+    /// The most children a node can get, unless unbounded.
+    pub fn max_children(&self) -> Option<u32> {
+        match self {
+            Offspring::BernoulliPair { .. } | Offspring::Binomial { .. } => Some(2),
+            Offspring::Geometric { p } if *p >= 1.0 => Some(0),
+            Offspring::Poisson { lambda } if *lambda == 0.0 => Some(0),
+            Offspring::Geometric { .. } | Offspring::Poisson { .. } => None,
+            Offspring::Table(weights) => {
+                Some(weights.iter().rposition(|w| *w > 0.0).unwrap_or(0) as u32)
+            }
+        }
+    }
+}
+impl OffspringDistribution for Offspring {
+    fn sample(&self, rng: &mut dyn RngCore) -> u32 {
+        match self {
+            Offspring::BernoulliPair { n, m } => {
+                if rng.random_range(0..*m) < m - n {
+                    0
+                } else if rng.random_range(0..*m) < *n {
+                    2
+                } else {
+                    1
+                }
+            }
+            Offspring::Binomial { p } => rng.random_bool(*p) as u32 + rng.random_bool(*p) as u32,
+            Offspring::Geometric { p } => {
+                if *p >= 1.0 {
+                    0
+                } else {
+                    ((-rng.random::<f64>()).ln_1p() / (-p).ln_1p()) as u32
+                }
+            }
+            // Multiplying uniforms until the product drops below e^-λ takes
+            // λ of them, and e^-λ is 0 past λ = 745.
+            Offspring::Poisson { lambda } if *lambda < 10.0 => {
+                let (limit, mut product, mut k) = ((-lambda).exp(), rng.random::<f64>(), 0);
+                while product > limit {
+                    product *= rng.random::<f64>();
+                    k += 1;
+                }
+                k
+            }
+            Offspring::Poisson { lambda } => poisson_ptrs(*lambda, rng),
+            Offspring::Table(weights) => {
+                let mut target = rng.random::<f64>() * weights.iter().sum::<f64>();
+                for (k, w) in weights.iter().enumerate() {
+                    if target < *w {
+                        return k as u32;
+                    }
+                    target -= w;
+                }
+                weights.iter().rposition(|w| *w > 0.0).unwrap_or(0) as u32
+            }
+        }
+    }
+    fn probability(&self, k: u32) -> f64 {
+        match self {
+            Offspring::BernoulliPair { n, m } => {
+                let p = *n as f64 / *m as f64;
+                match k {
+                    0 => 1.0 - p,
+                    1 => p * (1.0 - p),
+                    2 => p * p,
+                    _ => 0.0,
+                }
+            }
+            Offspring::Binomial { p } => match k {
+                0 => (1.0 - p) * (1.0 - p),
+                1 => 2.0 * p * (1.0 - p),
+                2 => p * p,
+                _ => 0.0,
+            },
+            Offspring::Geometric { p } => (1.0 - p).powi(k as i32) * p,
+            Offspring::Poisson { lambda } if *lambda == 0.0 => (k == 0) as u32 as f64,
+            Offspring::Poisson { lambda } => {
+                (k as f64 * lambda.ln() - lambda - ln_factorial(k as f64)).exp()
+            }
+            Offspring::Table(weights) => {
+                weights.get(k as usize).copied().unwrap_or(0.0) / weights.iter().sum::<f64>()
+            }
+        }
+    }
+    fn mean(&self) -> f64 {
+        match self {
+            Offspring::BernoulliPair { n, m } => {
+                let p = *n as f64 / *m as f64;
+                p + p * p
+            }
+            Offspring::Binomial { p } => 2.0 * p,
+            Offspring::Geometric { p } => (1.0 - p) / p,
+            Offspring::Poisson { lambda } => *lambda,
+            Offspring::Table(_) => terms(self).map(|(k, p)| k as f64 * p).sum(),
+        }
+    }
+    fn variance(&self) -> f64 {
+        match self {
+            Offspring::BernoulliPair { n, m } => {
+                let p = *n as f64 / *m as f64;
+                p + 3.0 * p * p - (p + p * p).powi(2)
+            }
+            Offspring::Binomial { p } => 2.0 * p * (1.0 - p),
+            Offspring::Geometric { p } => (1.0 - p) / (p * p),
+            Offspring::Poisson { lambda } => *lambda,
+            Offspring::Table(_) => {
+                let mean = self.mean();
+                terms(self)
+                    .map(|(k, p)| (k as f64 - mean).powi(2) * p)
+                    .sum()
+            }
+        }
+    }
+}
+
+/// Draws a Poisson(λ) number for λ of 10 or more in constant expected time,
+/// by Hörmann's transformed rejection with squeeze (PTRS).
+fn poisson_ptrs(lambda: f64, rng: &mut dyn RngCore) -> u32 {
+    let b = 0.931 + 2.53 * lambda.sqrt();
+    let a = -0.059 + 0.02483 * b;
+    let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+    let v_r = 0.9277 - 3.6224 / (b - 2.0);
+    loop {
+        let u = rng.random::<f64>() - 0.5;
+        let v = rng.random::<f64>();
+        let us = 0.5 - u.abs();
+        let k = ((2.0 * a / us + b) * u + lambda + 0.43).floor();
+        if us >= 0.07 && v <= v_r {
+            return k as u32;
+        }
+        if k < 0.0 || (us < 0.013 && v > us) {
+            continue;
+        }
+        if (v * inv_alpha / (a / (us * us) + b)).ln() <= k * lambda.ln() - lambda - ln_factorial(k)
+        {
+            return k as u32;
+        }
+    }
+}
+
+/// `ln k!`, exactly up to 10 and by Stirling's series, to within 1e-12,
+/// beyond.
+fn ln_factorial(k: f64) -> f64 {
+    if k < 10.0 {
+        return (2..=k as u32).map(|i| (i as f64).ln()).sum();
+    }
+    let k2 = k * k;
+    k * k.ln() - k + 0.5 * (std::f64::consts::TAU * k).ln() + 1.0 / (12.0 * k)
+        - 1.0 / (360.0 * k * k2)
+        + 1.0 / (1260.0 * k * k2 * k2)
+}
+impl std::fmt::Display for Offspring {
+    /// The spec accepted by [`FromStr`](std::str::FromStr).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Offspring::BernoulliPair { n, m } => write!(f, "bernoulli-pair:{n}/{m}"),
+            Offspring::Binomial { p } => write!(f, "binomial:{p}"),
+            Offspring::Geometric { p } => write!(f, "geometric:{p}"),
+            Offspring::Poisson { lambda } => write!(f, "poisson:{lambda}"),
+            Offspring::Table(weights) => write!(
+                f,
+                "table:{}",
+                weights
+                    .iter()
+                    .map(|w| w.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            ),
+        }
+    }
+}
+impl std::str::FromStr for Offspring {
+    type Err = &'static str;
+    /// Parses `bernoulli-pair:n/m`, `binomial:p`, `geometric:p`,
+    /// `poisson:λ` or `table:w0/w1/...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const USAGE: &str = "offspring law must be bernoulli-pair:n/m, binomial:p, \
+                             geometric:p, poisson:λ or table:w0/w1/...";
+        let (name, params) = s.trim().split_once(':').ok_or(USAGE)?;
+        let float = |s: &str| s.trim().parse::<f64>().map_err(|_| USAGE);
+        let law = match name.trim().to_ascii_lowercase().as_str() {
+            "bernoulli-pair" => {
+                let (n, m) = params.split_once('/').ok_or(USAGE)?;
+                Offspring::BernoulliPair {
+                    n: n.trim().parse().map_err(|_| USAGE)?,
+                    m: m.trim().parse().map_err(|_| USAGE)?,
+                }
+            }
+            "binomial" => Offspring::Binomial { p: float(params)? },
+            "geometric" => Offspring::Geometric { p: float(params)? },
+            "poisson" => Offspring::Poisson {
+                lambda: float(params)?,
+            },
+            "table" => Offspring::Table(params.split('/').map(float).collect::<Result<_, _>>()?),
+            _ => return Err(USAGE),
+        };
+        law.validate()?;
+        Ok(law)
+    }
+}
@@ -0,0 +1,96 @@
+//! Generation profiles: how many nodes each generation of a tree holds.
+use crate::{metrics::TreeMetrics, stats::Moments, tree::Tree};
+
+/// The leaves and branches of each generation of one tree, root first.
+///
+/// A generation holds at most every node of the tree's arena, whose
+/// indices are u32, so the widths are too; a [`MeanProfile`] sums them wider.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Profile {
+    pub leaves: Vec<u32>,
+    pub branches: Vec<u32>,
+}
+impl Profile {
+    /// Counts the generations of `tree`; [`TreeMetrics`] has this with
+    /// every other metric.
+    pub fn new(tree: &Tree) -> Self {
+        TreeMetrics::without_rolls(tree).profile
+    }
+    /// The size Z_k of each generation k.
+    pub fn widths(&self) -> impl Iterator<Item = u32> + '_ {
+        self.leaves.iter().zip(&self.branches).map(|(l, b)| l + b)
+    }
+}
+
+/// Mean size of one generation over a sample.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationMean {
+    pub leaves: f64,
+    pub branches: f64,
+    /// Mean of Z_k.
+    pub width: f64,
+    /// Population standard deviation of Z_k.
+    pub std_dev: f64,
+    /// The 95% confidence band of the mean width.
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// The mean [`Profile`] of a sample, with trees that died out earlier
+/// counting as empty generations.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeanProfile {
+    /// Trees averaged over.
+    pub samples: u64,
+    /// Every generation up to the deepest tree's last.
+    pub generations: Vec<GenerationMean>,
+}
+impl MeanProfile {
+    /// Averages `profiles`, with a normal confidence band of 1.96 standard
+    /// errors either side of each mean.
+    pub fn new(profiles: &[Profile]) -> Self {
+        let depth = profiles.iter().map(|p| p.leaves.len()).max().unwrap_or(0);
+        // Trees that died out before a generation count as zeros in it.
+        let mut reached = vec![0u64; depth];
+        for profile in profiles {
+            reached[..profile.leaves.len()]
+                .iter_mut()
+                .for_each(|r| *r += 1);
+        }
+        let mut moments: Vec<Moments> = reached
+            .iter()
+            .map(|&r| Moments::repeated(profiles.len() as u64 - r, 0.0))
+            .collect();
+        let mut leaves = vec![0u64; depth];
+        let mut branches = vec![0u64; depth];
+        for profile in profiles {
+            for (gen, width) in profile.widths().enumerate() {
+                leaves[gen] += profile.leaves[gen] as u64;
+                branches[gen] += profile.branches[gen] as u64;
+                moments[gen].push(width as f64);
+            }
+        }
+        let n = profiles.len() as f64;
+        let generations = (0..depth)
+            .map(|gen| {
+                let std_dev = moments[gen].variance().sqrt();
+                let width = (leaves[gen] + branches[gen]) as f64 / n;
+                let margin = 1.96 * std_dev / n.sqrt();
+                GenerationMean {
+                    leaves: leaves[gen] as f64 / n,
+                    branches: branches[gen] as f64 / n,
+                    width,
+                    std_dev,
+                    lower: width - margin,
+                    upper: width + margin,
+                }
+            })
+            .collect();
+        MeanProfile {
+            samples: profiles.len() as u64,
+            generations,
+        }
+    }
+}
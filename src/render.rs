@@ -0,0 +1,392 @@
+//! Text renderings of trees and stats.
+use crate::{
+    csv::Progress,
+    histogram::Histogram,
+    profile::{MeanProfile, Profile},
+    shape::Shape,
+    stats::{Stats, Summary},
+    theory::{relative_error, Prediction, Regime},
+    tree::Tree,
+};
+
+/// Draws `tree` with box characters, one node per line in depth-first order.
+pub fn print_tree(tree: &Tree) -> String {
+    let mut res = String::new();
+    // Whether the ancestor at each depth has siblings still to come.
+    let mut open: Vec<bool> = Vec::new();
+    let mut stack = vec![(0, 0, true)];
+    while let Some((node, gen, last)) = stack.pop() {
+        open.truncate(gen);
+        for &more in open.iter().skip(1) {
+            res.push_str(if more { "║" } else { " " });
+        }
+        open.push(!last);
+        let children = tree.children(node);
+        res.push_str(match (children.is_empty(), last) {
+            (false, _) if gen == 0 => "Root\n",
+            (true, true) => "╚Leaf\n",
+            (true, false) => "╠Leaf\n",
+            (false, true) => "╚Branch\n",
+            (false, false) => "╠Branch\n",
+        });
+        let end = children.end;
+        stack.extend(
+            children
+                .rev()
+                .map(|child| (child, gen + 1, child + 1 == end)),
+        );
+    }
+    res
+}
+/// The colour-coded child counts of generation `seek_gen`, left to right.
+pub fn get_nodes_at_generation(tree: &Tree, seek_gen: u32, colour: bool) -> String {
+    tree.generation(seek_gen)
+        .fold(String::new(), |mut acc, node| {
+            acc.push_str(&colour!(
+                colour,
+                match tree.child_count(node) {
+                    k @ 0..=9 => k.to_string(),
+                    k => format!("[{k}]"),
+                },
+                seek_gen % 8
+            ));
+            acc
+        })
+}
+/// The level-order child counts of every generation after the two fixed ones.
+pub fn tree_to_string(tree: &Tree, colour: bool) -> String {
+    (2..=tree.generations()).fold(String::new(), |mut acc, i| {
+        acc.push_str(&get_nodes_at_generation(tree, i, colour));
+        acc
+    })
+}
+/// The sizes and [`Shape`] indices of `tree`, one per line.
+pub fn get_tree_stats(tree: &Tree) -> String {
+    let shape = Shape::new(tree);
+    format!(
+        "Leaves: {}\n\
+	 Branches: {}\n\
+	 Nodes: {}\n\
+	 Generations: {}\n\
+	 Sackin: {}\n\
+	 Colless: {}\n\
+	 Cherries: {}\n\
+	 Path length: {} (average {:.3})\n\
+	 Horton–Strahler order: {}\n\
+	 Widths: {}",
+        tree.leaves(),
+        tree.branches(),
+        tree.nodes(),
+        tree.generations(),
+        shape.sackin,
+        shape.colless,
+        shape.cherries,
+        shape.path_length,
+        shape.average_path_length(),
+        shape.strahler,
+        shape
+            .widths
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+/// The rolls string followed by each generation on its own line, with its
+/// leaves and branches.
+pub fn get_tree_rolls(tree: &Tree, colour: bool) -> String {
+    let generations = tree.generations();
+    let profile = Profile::new(tree);
+    format!(
+        "Rolls: {}\n{}",
+        tree_to_string(tree, colour),
+        (0..=generations).fold(String::new(), |mut acc, i| {
+            acc.push_str(&format!(
+                "Gen {0:^1$}: {2} ({3} leaves, {4} branches)\n",
+                i,
+                format!("{generations}").len(),
+                get_nodes_at_generation(tree, i, colour),
+                profile.leaves[i as usize],
+                profile.branches[i as usize]
+            ));
+            acc
+        })
+    )
+}
+/// Stats of every metric with the change from the previous run.
+pub fn print_stats_delta(prev: &Stats, cur: &Stats, colour: bool) -> String {
+    stats_table(Some(prev), cur, colour)
+}
+/// Stats of every metric on their own.
+pub fn print_stats(stats: &Stats, colour: bool) -> String {
+    stats_table(None, stats, colour)
+}
+/// One row per statistic and one column per metric, with the sizes and the
+/// shape indices in separate tables.
+fn stats_table(prev: Option<&Stats>, cur: &Stats, colour: bool) -> String {
+    const WIDTH: usize = 20;
+    type Statistic = Box<dyn Fn(&Summary) -> Option<f64>>;
+    let mut rows: Vec<(String, bool, Statistic)> = vec![
+        ("Min".into(), true, Box::new(|s| Some(s.min as f64))),
+        ("Q1".into(), true, Box::new(|s| Some(s.q1 as f64))),
+        ("Median".into(), true, Box::new(|s| Some(s.median as f64))),
+        ("Q3".into(), true, Box::new(|s| Some(s.q3 as f64))),
+        ("Max".into(), true, Box::new(|s| Some(s.max as f64))),
+        ("Average".into(), false, Box::new(|s| Some(s.mean))),
+        ("σ".into(), false, Box::new(|s| Some(s.std_dev))),
+        ("Skewness".into(), false, Box::new(|s| Some(s.skewness))),
+        ("Kurtosis".into(), false, Box::new(|s| Some(s.kurtosis))),
+    ];
+    for &(p, _) in &cur.leaves.percentiles {
+        rows.push((
+            format!("P{p}"),
+            true,
+            Box::new(move |s| {
+                s.percentiles
+                    .iter()
+                    .find(|&&(q, _)| q == p)
+                    .map(|&(_, v)| v as f64)
+            }),
+        ));
+    }
+    let number = |x: f64, whole: bool| {
+        if whole {
+            format!("{x}")
+        } else {
+            format!("{x:.3}")
+        }
+    };
+    let mut res = String::new();
+    for (title, columns) in [("Tree stats", 0..4), ("Shape stats", 4..10)] {
+        res.push_str(&format!(
+            "{}{title}:\n\t{:10}",
+            if res.is_empty() { "" } else { "\n" },
+            ""
+        ));
+        for (name, _) in &cur.metrics()[columns.clone()] {
+            res.push_str(&format!("{name:WIDTH$}"));
+        }
+        for (name, whole, statistic) in &rows {
+            res.push_str(&format!("\n\t{name:10}"));
+            for i in columns.clone() {
+                let summary = cur.metrics()[i].1;
+                let value = statistic(summary).unwrap_or_default();
+                let before = prev.and_then(|prev| statistic(prev.metrics()[i].1));
+                let (text, tint) = match before {
+                    None => (number(value, *whole), 4),
+                    Some(before) => {
+                        let (arrow, tint) = match value.partial_cmp(&before) {
+                            Some(std::cmp::Ordering::Less) => ("↓", 1),
+                            Some(std::cmp::Ordering::Greater) => ("↑", 2),
+                            _ => ("=", 4),
+                        };
+                        (
+                            format!(
+                                "{} ({arrow}{})",
+                                number(value, *whole),
+                                number((value - before).abs(), *whole)
+                            ),
+                            tint,
+                        )
+                    }
+                };
+                res.push_str(&colour!(colour, format!("{text:WIDTH$}"), tint));
+            }
+        }
+    }
+    res
+}
+/// The predictions of the offspring law, next to the averages and the share
+/// of finite trees in a sample.
+pub fn print_prediction(prediction: &Prediction, stats: &Stats, colour: bool) -> String {
+    let finite = stats.finite();
+    let error = |empirical: f64, predicted: f64| {
+        let error = relative_error(empirical, predicted);
+        colour!(
+            colour,
+            format!("{:+.1}%", error * 100.0),
+            match error.abs() {
+                e if e < 0.05 => 2,
+                e if e < 0.2 => 3,
+                _ => 1,
+            }
+        )
+    };
+    let expected = |value: Option<f64>| match value {
+        Some(value) => colour!(colour, format!("{value:.3}"), 4),
+        None => colour!(colour, "∞", 1),
+    };
+    format!(
+        "Theory:\
+	 \n\tOffspring mean = {}, variance = {} ({})\
+	 \n\tExtinction probability = {} per line, {} per tree\
+	 \n\tFinite trees = {} ({})\
+	 \n\tExpected leaves = {} (average {}, {})\
+	 \n\tExpected nodes = {} (average {}, {})",
+        colour!(colour, format!("{:.3}", prediction.mean), 4),
+        colour!(colour, format!("{:.3}", prediction.variance), 4),
+        colour!(
+            colour,
+            prediction.regime,
+            match prediction.regime {
+                Regime::Subcritical => 2,
+                Regime::Critical => 3,
+                Regime::Supercritical => 1,
+            }
+        ),
+        colour!(colour, format!("{:.4}", prediction.extinction), 4),
+        colour!(colour, format!("{:.4}", prediction.finite), 4),
+        colour!(colour, format!("{finite:.4}"), 4),
+        if prediction.finite > 0.0 {
+            error(finite, prediction.finite)
+        } else {
+            String::from("-")
+        },
+        expected(prediction.leaves),
+        colour!(colour, format!("{:.3}", stats.leaves.mean), 4),
+        match prediction.leaves {
+            Some(leaves) => error(stats.leaves.mean, leaves),
+            None => String::from("-"),
+        },
+        expected(prediction.nodes),
+        colour!(colour, format!("{:.3}", stats.nodes.mean), 4),
+        match prediction.nodes {
+            Some(nodes) => error(stats.nodes.mean, nodes),
+            None => String::from("-"),
+        },
+    )
+}
+/// Draws `histogram` with one bar of block characters per bin, scaled to
+/// the fullest bin, or to the logarithm of the counts with `log`.
+pub fn print_histogram(name: &str, histogram: &Histogram, log: bool, colour: bool) -> String {
+    const WIDTH: f64 = 50.0;
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let scale = |count: u32| {
+        if log {
+            (count as f64).ln_1p()
+        } else {
+            count as f64
+        }
+    };
+    let top = histogram
+        .counts
+        .iter()
+        .copied()
+        .map(scale)
+        .fold(0.0, f64::max);
+    let labels: Vec<String> = histogram
+        .bins()
+        .map(|(from, to, _)| {
+            if from == to {
+                from.to_string()
+            } else {
+                format!("{from}-{to}")
+            }
+        })
+        .collect();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let mut res = format!("{name}{}:", if log { " (log scale)" } else { "" });
+    for (label, (_, _, count)) in labels.iter().zip(histogram.bins()) {
+        let eighths = if top > 0.0 {
+            (scale(count) / top * WIDTH * 8.0).round() as usize
+        } else {
+            0
+        };
+        res.push_str(&format!(
+            "\n\t{label:>label_width$} │{} {count}",
+            colour!(
+                colour,
+                format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8]),
+                4
+            )
+        ));
+    }
+    res
+}
+/// A one-line progress report for a sample export, with the time left at
+/// the rate so far, `elapsed` since the run started.
+pub fn print_progress(progress: &Progress, elapsed: std::time::Duration, colour: bool) -> String {
+    let left = progress.target - progress.rows;
+    let eta = if progress.added > 0 {
+        let secs = (elapsed.as_secs_f64() / progress.added as f64 * left as f64).round() as u64;
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        String::from("-")
+    };
+    format!(
+        "Written {}/{} samples ({:.1}%), ETA {}",
+        colour!(colour, progress.rows, 4),
+        colour!(colour, progress.target, 4),
+        100.0 * progress.rows as f64 / progress.target.max(1) as f64,
+        colour!(colour, eta, 4)
+    )
+}
+/// Block characters from lowest to highest, for sparklines.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// `values` as a line of block characters scaled to `top`, averaging
+/// neighbours so that it is at most `width` characters long.
+pub fn sparkline(values: &[f64], top: f64, width: usize) -> String {
+    let per = values.len().div_ceil(width.max(1)).max(1);
+    values
+        .chunks(per)
+        .map(|chunk| {
+            let value = chunk.iter().sum::<f64>() / chunk.len() as f64;
+            if top > 0.0 {
+                SPARKS[((value / top * 8.0) as usize).min(7)]
+            } else {
+                SPARKS[0]
+            }
+        })
+        .collect()
+}
+/// The mean generation sizes of a sample as sparklines next to the sizes
+/// `prediction` expects, then as a table of the first generations.
+pub fn print_profile(profile: &MeanProfile, prediction: &Prediction, colour: bool) -> String {
+    const WIDTH: usize = 60;
+    const ROWS: usize = 20;
+    let means: Vec<f64> = profile.generations.iter().map(|g| g.width).collect();
+    let expected: Vec<f64> = (0..means.len() as u32)
+        .map(|gen| prediction.width(gen))
+        .collect();
+    let top = means.iter().chain(&expected).copied().fold(0.0, f64::max);
+    let mut res = format!(
+        "Generation profile over {} trees (4·{}^(k-2) expected in generation k):\
+         \n\tMean      {}\
+         \n\tExpected  {}\
+         \n\t{:6}{:>10}{:>10}{:>10}{:>22}{:>10}",
+        colour!(colour, profile.samples, 4),
+        colour!(colour, format!("{:.3}", prediction.growth), 4),
+        colour!(colour, sparkline(&means, top, WIDTH), 4),
+        colour!(colour, sparkline(&expected, top, WIDTH), 2),
+        "Gen",
+        "Leaves",
+        "Branches",
+        "Width",
+        "95% band",
+        "Expected"
+    );
+    for (gen, mean) in profile.generations.iter().enumerate().take(ROWS) {
+        let expected = expected[gen];
+        // Green when the band covers the expected width.
+        let tint = if (mean.lower..=mean.upper).contains(&expected) {
+            2
+        } else {
+            3
+        };
+        res.push_str(&format!(
+            "\n\t{gen:<6}{:>10.3}{:>10.3}{}{:>22}{:>10.3}",
+            mean.leaves,
+            mean.branches,
+            colour!(colour, format!("{:>10.3}", mean.width), tint),
+            format!("{:.3} to {:.3}", mean.lower, mean.upper),
+            expected
+        ));
+    }
+    if profile.generations.len() > ROWS {
+        res.push_str(&format!(
+            "\n\t... and {} more generations",
+            profile.generations.len() - ROWS
+        ));
+    }
+    res
+}
@@ -0,0 +1,36 @@
+//! Shape indices of a tree, beyond its sizes.
+use crate::{metrics::TreeMetrics, tree::Tree};
+
+/// How balanced and bushy a tree is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Shape {
+    /// Sum of the depths of the leaves.
+    pub sackin: u64,
+    /// Sum over branches of how unevenly their leaves split between their
+    /// children: `|l - r|` for two children with `l` and `r` leaves below,
+    /// and the sum of that over every pair of children for more.
+    pub colless: u64,
+    /// Branches whose only children are two leaves.
+    pub cherries: u32,
+    /// Sum of the depths of all nodes.
+    pub path_length: u64,
+    /// Horton–Strahler order of the root: 1 for a leaf, and for a branch the
+    /// highest order of its children, plus one if two children share it.
+    pub strahler: u32,
+    /// Number of nodes in each generation, root first.
+    pub widths: Vec<u32>,
+}
+impl Shape {
+    /// Measures `tree`; [`TreeMetrics`] has this with every other metric.
+    pub fn new(tree: &Tree) -> Self {
+        TreeMetrics::without_rolls(tree).shape
+    }
+    /// The largest generation.
+    pub fn width(&self) -> u32 {
+        self.widths.iter().copied().max().unwrap_or(0)
+    }
+    /// Mean depth of a node.
+    pub fn average_path_length(&self) -> f64 {
+        self.path_length as f64 / self.widths.iter().map(|&w| w as f64).sum::<f64>()
+    }
+}
@@ -0,0 +1,284 @@
+//! Statistics over samples of generated trees.
+use crate::{
+    generator::{Generated, TreeGenerator},
+    histogram::Histogram,
+    metrics::TreeMetrics,
+    profile::{MeanProfile, Profile},
+    shape::Shape,
+    tree::Counts,
+};
+use rayon::prelude::*;
+
+/// Order statistics and moments of one metric over a sample.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    pub min: u64,
+    /// The 25th percentile.
+    pub q1: u64,
+    pub median: u64,
+    /// The 75th percentile.
+    pub q3: u64,
+    pub max: u64,
+    pub mean: f64,
+    /// Population standard deviation.
+    pub std_dev: f64,
+    pub skewness: f64,
+    /// Excess kurtosis, 0 for a normal distribution.
+    pub kurtosis: f64,
+    /// The requested percentiles, as `(p, value)`.
+    pub percentiles: Vec<(f64, u64)>,
+}
+impl Summary {
+    /// Summarises `values`, sorting them in place.
+    ///
+    /// Percentiles are between 0 and 100, and pick the nearest value. The
+    /// mean comes from an exact `u128` sum, and the other moments from one
+    /// pass of [`Moments`]; both work on the distances from the smallest
+    /// value, so that values near `u64::MAX` keep their differences.
+    pub fn new(values: &mut [u64], percentiles: &[f64]) -> Self {
+        if values.is_empty() {
+            return Summary {
+                percentiles: percentiles.iter().map(|&p| (p, 0)).collect(),
+                ..Summary::default()
+            };
+        }
+        values.sort_unstable();
+        let percentile = |p: f64| {
+            values[((p / 100.0).clamp(0.0, 1.0) * (values.len() - 1) as f64).round() as usize]
+        };
+        let min = values[0];
+        let mut moments = Moments::default();
+        let mut sum = 0u128;
+        for &x in values.iter() {
+            moments.push((x - min) as f64);
+            sum += (x - min) as u128;
+        }
+        Summary {
+            min,
+            q1: percentile(25.0),
+            median: percentile(50.0),
+            q3: percentile(75.0),
+            max: values[values.len() - 1],
+            mean: min as f64 + (sum as f64 / values.len() as f64),
+            std_dev: moments.variance().sqrt(),
+            skewness: moments.skewness(),
+            kurtosis: moments.kurtosis(),
+            percentiles: percentiles.iter().map(|&p| (p, percentile(p))).collect(),
+        }
+    }
+}
+
+/// Running mean and central moments of a stream of values.
+///
+/// Welford's update, extended to the third and fourth moments, moves the
+/// mean by each value's share of its distance from it, so no large sums are
+/// kept that could overflow or cancel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Moments {
+    pub count: u64,
+    pub mean: f64,
+    /// Sums of the 2nd, 3rd and 4th powers of the distances from the mean.
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+impl Moments {
+    /// A stream of `count` copies of `x`.
+    pub fn repeated(count: u64, x: f64) -> Self {
+        Moments {
+            count,
+            mean: if count > 0 { x } else { 0.0 },
+            ..Default::default()
+        }
+    }
+    /// Adds `x` to the stream.
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = x - self.mean;
+        let share = delta / n;
+        let share2 = share * share;
+        let term = delta * share * (n - 1.0);
+        self.mean += share;
+        self.m4 += term * share2 * (n * n - 3.0 * n + 3.0) + 6.0 * share2 * self.m2
+            - 4.0 * share * self.m3;
+        self.m3 += term * share * (n - 2.0) - 3.0 * share * self.m2;
+        self.m2 += term;
+    }
+    /// Population variance.
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+    /// 0 when every value is the same.
+    pub fn skewness(&self) -> f64 {
+        if self.m2 > 0.0 {
+            (self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5)
+        } else {
+            0.0
+        }
+    }
+    /// Excess kurtosis, 0 when every value is the same.
+    pub fn kurtosis(&self) -> f64 {
+        if self.m2 > 0.0 {
+            self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Summaries of every tree metric over a sample.
+///
+/// Metrics missing from stats files of older versions are left empty.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Stats {
+    /// Trees drawn, censored ones included.
+    pub samples: u64,
+    /// Trees left out of the summaries for hitting the size limits.
+    pub censored: u64,
+    pub leaves: Summary,
+    pub branches: Summary,
+    pub nodes: Summary,
+    pub generations: Summary,
+    pub sackin: Summary,
+    pub colless: Summary,
+    pub cherries: Summary,
+    pub path_length: Summary,
+    pub strahler: Summary,
+    /// Of the largest generation of each tree.
+    pub width: Summary,
+    pub profile: MeanProfile,
+}
+impl Stats {
+    /// The share of the sample that was not censored.
+    pub fn finite(&self) -> f64 {
+        (self.samples - self.censored) as f64 / self.samples.max(1) as f64
+    }
+    /// The summaries with their names, sizes first, then shape indices.
+    pub fn metrics(&self) -> [(&'static str, &Summary); 10] {
+        [
+            ("Leaves", &self.leaves),
+            ("Branches", &self.branches),
+            ("Nodes", &self.nodes),
+            ("Generations", &self.generations),
+            ("Sackin", &self.sackin),
+            ("Colless", &self.colless),
+            ("Cherries", &self.cherries),
+            ("Path length", &self.path_length),
+            ("Strahler", &self.strahler),
+            ("Width", &self.width),
+        ]
+    }
+}
+
+/// The metrics of every tree in a sample that was not censored, in the
+/// order they were drawn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sample {
+    /// Trees drawn, censored ones included.
+    pub samples: u64,
+    pub censored: u64,
+    pub leaves: Vec<u64>,
+    pub branches: Vec<u64>,
+    pub nodes: Vec<u64>,
+    pub generations: Vec<u64>,
+    pub sackin: Vec<u64>,
+    pub colless: Vec<u64>,
+    pub cherries: Vec<u64>,
+    pub path_length: Vec<u64>,
+    pub strahler: Vec<u64>,
+    pub width: Vec<u64>,
+    pub profiles: Vec<Profile>,
+}
+impl Sample {
+    /// Draws `sample_size` trees from `generator`, across all threads, and
+    /// measures their sizes, [`Shape`]s and [`Profile`]s.
+    ///
+    /// Each tree draws from its own [`sample_rng`](TreeGenerator::sample_rng),
+    /// so the sample does not depend on the number of threads. Reseeding
+    /// RNGs are not reproducible anyway, and every tree drawn on a worker
+    /// thread shares that thread's one.
+    pub fn draw(generator: &TreeGenerator, sample_size: u32) -> Self {
+        let trees: Vec<Generated<(Counts, Shape, Profile)>> = (0..sample_size)
+            .into_par_iter()
+            .map_init(
+                || generator.make_rng(),
+                |rng, i| {
+                    rng.set_stream(i as u64);
+                    match generator.generate_with(rng) {
+                        Generated::Complete(tree) => {
+                            let metrics = TreeMetrics::without_rolls(&tree);
+                            Generated::Complete((metrics.counts, metrics.shape, metrics.profile))
+                        }
+                        Generated::Censored(_) => Generated::Censored(Default::default()),
+                    }
+                },
+            )
+            .collect();
+        let mut sample = Sample {
+            samples: sample_size as u64,
+            ..Sample::default()
+        };
+        for tree in trees {
+            let Generated::Complete((counts, shape, profile)) = tree else {
+                sample.censored += 1;
+                continue;
+            };
+            sample.leaves.push(counts.leaves);
+            sample.branches.push(counts.branches);
+            sample.nodes.push(counts.nodes);
+            sample.generations.push(counts.generations);
+            sample.sackin.push(shape.sackin);
+            sample.colless.push(shape.colless);
+            sample.cherries.push(shape.cherries as u64);
+            sample.path_length.push(shape.path_length);
+            sample.strahler.push(shape.strahler as u64);
+            sample.width.push(shape.width() as u64);
+            sample.profiles.push(profile);
+        }
+        sample
+    }
+    /// Summarises each metric, with the given `percentiles`.
+    pub fn stats(&self, percentiles: &[f64]) -> Stats {
+        let summary = |values: &[u64]| Summary::new(&mut values.to_vec(), percentiles);
+        Stats {
+            samples: self.samples,
+            censored: self.censored,
+            leaves: summary(&self.leaves),
+            branches: summary(&self.branches),
+            nodes: summary(&self.nodes),
+            generations: summary(&self.generations),
+            sackin: summary(&self.sackin),
+            colless: summary(&self.colless),
+            cherries: summary(&self.cherries),
+            path_length: summary(&self.path_length),
+            strahler: summary(&self.strahler),
+            width: summary(&self.width),
+            profile: MeanProfile::new(&self.profiles),
+        }
+    }
+    /// Histograms of the leaves, generations and nodes, with at most `bins`
+    /// bins each.
+    pub fn histograms(&self, bins: u32) -> [(&'static str, Histogram); 3] {
+        [
+            ("Leaves", Histogram::new(&self.leaves, bins)),
+            ("Generations", Histogram::new(&self.generations, bins)),
+            ("Nodes", Histogram::new(&self.nodes, bins)),
+        ]
+    }
+}
+
+/// Draws `sample_size` trees from `generator`, as [`Sample::draw`] does, and
+/// summarises each metric, with the given `percentiles`.
+///
+/// Censored trees are left out of the summaries and only counted.
+pub fn check_stats(generator: &TreeGenerator, sample_size: u32, percentiles: &[f64]) -> Stats {
+    Sample::draw(generator, sample_size).stats(percentiles)
+}
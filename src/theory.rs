@@ -0,0 +1,124 @@
+//! What the offspring law predicts about the trees it grows.
+//!
+//! Past the two fixed generations, a tree is four independent Galton–Watson
+//! processes, one from each node of generation 2.
+use crate::offspring::{terms, OffspringDistribution};
+
+/// Whether the mean number of children is below, at or above one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Regime {
+    Subcritical,
+    Critical,
+    Supercritical,
+}
+impl std::fmt::Display for Regime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Regime::Subcritical => "subcritical",
+            Regime::Critical => "critical",
+            Regime::Supercritical => "supercritical",
+        })
+    }
+}
+
+/// Predictions for a whole tree.
+///
+/// The expected counts are over finite trees, so they compare with samples
+/// that leave censored trees out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    /// Mean number of children.
+    pub mean: f64,
+    /// Variance of the number of children.
+    pub variance: f64,
+    pub regime: Regime,
+    /// Probability that the line of one node of generation 2 dies out: the
+    /// smallest fixed point of the generating function.
+    pub extinction: f64,
+    /// Probability that the whole tree is finite.
+    pub finite: f64,
+    /// Expected leaves of a finite tree, unless infinite.
+    pub leaves: Option<f64>,
+    /// Expected nodes of a finite tree, unless infinite.
+    pub nodes: Option<f64>,
+    /// Mean children of a node whose line dies out, f'(q): the mean itself
+    /// unless supercritical.
+    pub growth: f64,
+}
+impl Prediction {
+    /// What the theory of branching processes expects from `law`.
+    pub fn new(law: &(impl OffspringDistribution + ?Sized)) -> Self {
+        let mean = law.mean();
+        let regime = if (mean - 1.0).abs() < 1e-9 {
+            Regime::Critical
+        } else if mean < 1.0 {
+            Regime::Subcritical
+        } else {
+            Regime::Supercritical
+        };
+        let extinction = if law.probability(1) >= 1.0 {
+            0.0
+        } else if regime == Regime::Supercritical {
+            // Newton's method on the convex f(s) - s climbs from 0 to the
+            // smallest root without overshooting.
+            let mut s = 0.0;
+            for _ in 0..100 {
+                let step = (generating_function(law, s) - s) / (1.0 - derivative(law, s));
+                s += step;
+                if step < 1e-15 {
+                    break;
+                }
+            }
+            s
+        } else {
+            1.0
+        };
+        // Conditioned on dying out, a line is a Galton–Watson process with
+        // mean f'(q), and a node is a leaf with probability P(0) / q.
+        let slope = derivative(law, extinction);
+        let (leaves, nodes) = if extinction > 0.0 && slope < 1.0 - 1e-9 {
+            let size = 1.0 / (1.0 - slope);
+            (
+                Some(4.0 * law.probability(0) / extinction * size),
+                Some(3.0 + 4.0 * size),
+            )
+        } else {
+            (None, None)
+        };
+        Prediction {
+            mean,
+            variance: law.variance(),
+            regime,
+            extinction,
+            finite: extinction.powi(4),
+            leaves,
+            nodes,
+            growth: slope,
+        }
+    }
+    /// Expected size Z_k of generation `gen` of a finite tree: 1 and 2 for
+    /// the fixed generations, then `4 * growth^(k - 2)`, which is the m^k
+    /// growth of a Galton–Watson process unless supercritical.
+    pub fn width(&self, gen: u32) -> f64 {
+        match gen {
+            0 => 1.0,
+            1 => 2.0,
+            _ => 4.0 * self.growth.powi(gen as i32 - 2),
+        }
+    }
+}
+
+/// `(empirical - predicted) / predicted`.
+pub fn relative_error(empirical: f64, predicted: f64) -> f64 {
+    (empirical - predicted) / predicted
+}
+
+fn generating_function(law: &(impl OffspringDistribution + ?Sized), s: f64) -> f64 {
+    terms(law).map(|(k, p)| p * s.powi(k as i32)).sum()
+}
+fn derivative(law: &(impl OffspringDistribution + ?Sized), s: f64) -> f64 {
+    terms(law)
+        .skip(1)
+        .map(|(k, p)| k as f64 * p * s.powi(k as i32 - 1))
+        .sum()
+}
@@ -0,0 +1,359 @@
+//! Tree representation and counters.
+
+/// A node of a tree. A branch has at least one child.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Leaf,
+    Branch(Vec<Node>),
+}
+impl TryFrom<Node> for Vec<Node> {
+    type Error = &'static str;
+    fn try_from(mut value: Node) -> Result<Self, Self::Error> {
+        match &mut value {
+            Node::Branch(children) => Ok(std::mem::take(children)),
+            _ => Err("Not a branch"),
+        }
+    }
+}
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        if let Node::Branch(children) = self {
+            stack.append(children);
+        }
+        while let Some(mut node) = stack.pop() {
+            if let Node::Branch(children) = &mut node {
+                stack.append(children);
+            }
+        }
+    }
+}
+
+/// The sizes of a tree, wide enough for trees of boxed nodes and for sums
+/// over samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counts {
+    pub leaves: u64,
+    pub branches: u64,
+    pub nodes: u64,
+    pub generations: u64,
+}
+
+/// A node's children, as a run of consecutive node indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Slot {
+    first_child: u32,
+    children: u32,
+}
+
+/// A whole tree, stored as an arena of nodes in level order.
+///
+/// Nodes are numbered from 0 at the root, generation by generation and left
+/// to right within each, so the children of every node, and every
+/// generation, are runs of consecutive indices. Indices are `u32`, so a tree
+/// has fewer than 2^32 nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    slots: Vec<Slot>,
+    /// Index of the first node of each generation.
+    starts: Vec<u32>,
+}
+impl Tree {
+    pub(crate) fn empty() -> Self {
+        Tree {
+            slots: Vec::new(),
+            starts: Vec::new(),
+        }
+    }
+    /// Begins the next generation; it must get as many nodes as the
+    /// previous one has children.
+    pub(crate) fn start_generation(&mut self) {
+        self.starts.push(self.slots.len() as u32);
+    }
+    /// Appends a node with `children` children to the current generation.
+    pub(crate) fn push(&mut self, children: u32) {
+        let first_child = self
+            .slots
+            .last()
+            .map_or(1, |slot| slot.first_child + slot.children);
+        self.slots.push(Slot {
+            first_child,
+            children,
+        });
+    }
+    /// Turns every node of the current generation into a leaf.
+    pub(crate) fn prune_generation(&mut self) {
+        let start = *self.starts.last().unwrap() as usize;
+        let first_child = self.slots[start].first_child;
+        for slot in &mut self.slots[start..] {
+            *slot = Slot {
+                first_child,
+                children: 0,
+            };
+        }
+    }
+    // Nodes are indexed by u32, so these sizes always fit in one and stay
+    // u32 for walking the arena. Outside the crate, `counts` gives them as
+    // u64, like every other count.
+    /// Number of nodes without children.
+    pub(crate) fn leaves(&self) -> u32 {
+        self.slots.iter().filter(|slot| slot.children == 0).count() as u32
+    }
+    /// Number of nodes with children.
+    pub(crate) fn branches(&self) -> u32 {
+        self.nodes() - self.leaves()
+    }
+    /// Number of nodes, root included.
+    pub(crate) fn nodes(&self) -> u32 {
+        self.slots.len() as u32
+    }
+    /// Depth of the deepest leaf.
+    pub(crate) fn generations(&self) -> u32 {
+        self.starts.len() as u32 - 1
+    }
+    /// The numbers of leaves, branches and nodes, and the depth of the
+    /// deepest leaf.
+    pub fn counts(&self) -> Counts {
+        Counts {
+            leaves: self.leaves() as u64,
+            branches: self.branches() as u64,
+            nodes: self.nodes() as u64,
+            generations: self.generations() as u64,
+        }
+    }
+    /// Indices of the nodes of generation `gen`.
+    pub fn generation(&self, gen: u32) -> std::ops::Range<u32> {
+        match self.starts.get(gen as usize) {
+            Some(&start) => {
+                start
+                    ..self
+                        .starts
+                        .get(gen as usize + 1)
+                        .copied()
+                        .unwrap_or(self.nodes())
+            }
+            None => self.nodes()..self.nodes(),
+        }
+    }
+    /// Indices of the children of `node`.
+    pub fn children(&self, node: u32) -> std::ops::Range<u32> {
+        let slot = self.slots[node as usize];
+        slot.first_child..slot.first_child + slot.children
+    }
+    /// Number of children of `node`.
+    pub fn child_count(&self, node: u32) -> u32 {
+        self.slots[node as usize].children
+    }
+    /// Whether the tree starts with the two fixed generations of pairs, as
+    /// every generated tree does and its [`rolls`](Tree::rolls) assume.
+    pub fn has_fixed_generations(&self) -> bool {
+        self.generations() >= 2
+            && (0..=1).all(|gen| self.generation(gen).all(|node| self.child_count(node) == 2))
+    }
+    /// The same tree with boxed nodes.
+    pub fn to_node(&self) -> Node {
+        let mut below: Vec<Node> = Vec::new();
+        for gen in (0..=self.generations()).rev() {
+            let mut children = below.into_iter();
+            below = self
+                .generation(gen)
+                .map(|node| match self.child_count(node) {
+                    0 => Node::Leaf,
+                    count => Node::Branch(children.by_ref().take(count as usize).collect()),
+                })
+                .collect();
+        }
+        below.pop().unwrap()
+    }
+    /// The level-order encoding written to the `rolls` column: the number
+    /// of children of each node, one digit each, or `[k]` from ten up.
+    pub fn rolls(&self) -> String {
+        let mut rolls = String::new();
+        for node in self.generation(2).start..self.nodes() {
+            match self.child_count(node) {
+                k @ 0..=9 => rolls.push(char::from(b'0' + k as u8)),
+                k => rolls.push_str(&format!("[{k}]")),
+            }
+        }
+        rolls
+    }
+    /// Rebuilds a tree from its [`rolls`](Tree::rolls).
+    ///
+    /// Generations 0 and 1 are implicit pairs of branches, so the string
+    /// starts with the four nodes of generation 2, and must end on a
+    /// generation of leaves.
+    pub fn from_rolls(rolls: &str) -> Result<Self, RollsError> {
+        let mut counts = Vec::new();
+        let mut i = 0;
+        while i < rolls.len() {
+            let position = i;
+            let count = match rolls.as_bytes()[i] {
+                digit @ b'0'..=b'9' => {
+                    i += 1;
+                    Some((digit - b'0') as u32)
+                }
+                b'[' => rolls[i + 1..].split_once(']').and_then(|(digits, _)| {
+                    i += digits.len() + 2;
+                    digits.parse().ok()
+                }),
+                _ => None,
+            };
+            let found = rolls[position..].chars().next().unwrap();
+            counts.push((
+                position,
+                count.ok_or(RollsError::InvalidChar { position, found })?,
+            ));
+        }
+        let mut levels = vec![vec![2], vec![2, 2]];
+        let (mut pos, mut width) = (0, 4);
+        while width > 0 {
+            if pos + width > counts.len() {
+                return Err(RollsError::Truncated {
+                    generation: levels.len() as u32,
+                    expected: width,
+                    found: counts.len() - pos,
+                });
+            }
+            let level: Vec<u32> = counts[pos..pos + width].iter().map(|&(_, k)| k).collect();
+            pos += width;
+            width = level.iter().map(|&k| k as usize).sum();
+            levels.push(level);
+        }
+        if let Some(&(position, _)) = counts.get(pos) {
+            return Err(RollsError::Trailing { position });
+        }
+        Ok(from_levels(&levels))
+    }
+    /// Rebuilds a binary tree from the 0/1 rolls of version 1 files, where
+    /// each `1` is a branch with two children.
+    pub fn from_binary_rolls(rolls: &str) -> Result<Self, RollsError> {
+        match rolls.char_indices().find(|&(_, c)| !matches!(c, '0' | '1')) {
+            Some((position, found)) => Err(RollsError::InvalidChar { position, found }),
+            None => Tree::from_rolls(&rolls.replace('1', "2")),
+        }
+    }
+}
+impl Default for Tree {
+    /// A lone leaf.
+    fn default() -> Self {
+        Tree::from(&Node::Leaf)
+    }
+}
+impl From<&Node> for Tree {
+    fn from(root: &Node) -> Self {
+        let mut tree = Tree::empty();
+        let mut level = vec![root];
+        while !level.is_empty() {
+            tree.start_generation();
+            let mut next = Vec::new();
+            for node in level {
+                match node {
+                    Node::Leaf => tree.push(0),
+                    Node::Branch(children) => {
+                        tree.push(children.len() as u32);
+                        next.extend(children);
+                    }
+                }
+            }
+            level = next;
+        }
+        tree
+    }
+}
+impl From<Node> for Tree {
+    fn from(root: Node) -> Self {
+        Tree::from(&root)
+    }
+}
+
+/// Number of leaves of a boxed tree.
+pub fn count_leaves(tree: &Node) -> u64 {
+    let mut count = 0;
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Leaf => count += 1,
+            Node::Branch(children) => stack.extend(children.iter().rev()),
+        }
+    }
+    count
+}
+/// Number of branches of a boxed tree.
+pub fn count_branches(tree: &Node) -> u64 {
+    let mut count = 0;
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        if let Node::Branch(children) = node {
+            count += 1;
+            stack.extend(children.iter().rev());
+        }
+    }
+    count
+}
+/// Number of nodes of a boxed tree.
+pub fn count_nodes(tree: &Node) -> u64 {
+    count_leaves(tree) + count_branches(tree)
+}
+/// Depth of the deepest leaf of a boxed tree.
+pub fn count_generations(tree: &Node) -> u64 {
+    let mut max = 0;
+    let mut stack = vec![(tree, 0)];
+    while let Some((node, gen)) = stack.pop() {
+        match node {
+            Node::Leaf => max = max.max(gen),
+            Node::Branch(children) => {
+                stack.extend(children.iter().rev().map(|child| (child, gen + 1)))
+            }
+        }
+    }
+    max
+}
+
+/// Assembles a tree from the child counts of each generation, root first.
+pub(crate) fn from_levels(levels: &[Vec<u32>]) -> Tree {
+    let mut tree = Tree::empty();
+    for level in levels {
+        tree.start_generation();
+        for &count in level {
+            tree.push(count);
+        }
+    }
+    tree
+}
+
+/// Why a rolls string could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RollsError {
+    /// A character that does not start a child count, at byte `position`.
+    InvalidChar { position: usize, found: char },
+    /// The string ends `expected - found` rolls short of the end of `generation`.
+    Truncated {
+        generation: u32,
+        expected: usize,
+        found: usize,
+    },
+    /// Rolls left over from byte `position`, after a generation of leaves.
+    Trailing { position: usize },
+}
+impl std::fmt::Display for RollsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollsError::InvalidChar { position, found } => {
+                write!(f, "invalid roll {found:?} at position {position}")
+            }
+            RollsError::Truncated {
+                generation,
+                expected,
+                found,
+            } => write!(
+                f,
+                "rolls end early: generation {generation} needs {expected} rolls, found {found}"
+            ),
+            RollsError::Trailing { position } => write!(
+                f,
+                "unexpected rolls from position {position} after a generation of leaves"
+            ),
+        }
+    }
+}
+impl std::error::Error for RollsError {}
@@ -0,0 +1,95 @@
+use std::io::Cursor;
+use thin_tree::{
+    archive::{self, Archive, ArchiveWriter},
+    csv::{self, LoadError},
+    Generated, Offspring, RngStrategy, Tree, TreeGenerator,
+};
+
+#[test]
+fn round_trip() {
+    let generator = TreeGenerator::from(Offspring::Poisson { lambda: 0.9 })
+        .rng(RngStrategy::Secure)
+        .seed(5)
+        .max_nodes(2000);
+    let trees: Vec<_> = (0..100)
+        .map(|i| generator.generate_with(&mut generator.sample_rng(i)))
+        .chain([Tree::default(), Tree::from_rolls("0300000").unwrap()].map(Generated::Complete))
+        .collect();
+    let mut writer = ArchiveWriter::new(Vec::new(), Some(&generator)).unwrap();
+    for tree in &trees {
+        writer.push(tree.tree(), tree.is_censored()).unwrap();
+    }
+    let bytes = writer.finish().unwrap();
+    let mut archive = Archive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.origin, Some(generator));
+    assert_eq!(archive.len(), trees.len());
+    assert_eq!(
+        archive.censored as usize,
+        trees.iter().filter(|t| t.is_censored()).count()
+    );
+    for row in (1..=trees.len() as u32).rev() {
+        assert_eq!(archive.tree(row).unwrap(), trees[row as usize - 1]);
+    }
+    assert!(matches!(archive.tree(0), Err(LoadError::NoRow(0))));
+}
+
+#[test]
+fn csv_conversion() {
+    let generator = TreeGenerator::new(60, 100).seed(8).max_nodes(500);
+    let dir = std::env::temp_dir();
+    let (sample, packed, unpacked) = (
+        dir.join("thin-tree-archive-sample.csv"),
+        dir.join("thin-tree-archive.ttb"),
+        dir.join("thin-tree-archive-unpacked.csv"),
+    );
+    let censored = csv::write_samples(&sample, &generator, 300, true).unwrap();
+    assert_eq!(archive::from_csv(&sample, &packed).unwrap(), 300);
+    assert_eq!(archive::to_csv(&packed, &unpacked).unwrap(), 300);
+    let sample = std::fs::read_to_string(&sample).unwrap();
+    assert_eq!(std::fs::read_to_string(&unpacked).unwrap(), sample);
+    assert!(std::fs::metadata(&packed).unwrap().len() * 3 < sample.len() as u64);
+
+    assert_eq!(
+        archive::write_samples(&packed, &generator, 300).unwrap(),
+        censored
+    );
+    archive::to_csv(&packed, &unpacked).unwrap();
+    assert_eq!(std::fs::read_to_string(&unpacked).unwrap(), sample);
+}
+
+#[test]
+fn corrupt() {
+    let mut writer = ArchiveWriter::new(Vec::new(), None).unwrap();
+    writer
+        .push(&Tree::from_rolls("200000").unwrap(), false)
+        .unwrap();
+    let bytes = writer.finish().unwrap();
+    for bad in [
+        b"THINTREX".to_vec(),
+        bytes[..bytes.len() - 1].to_vec(),
+        bytes[..20].to_vec(),
+    ] {
+        assert!(Archive::new(Cursor::new(bad)).is_err());
+    }
+    // Records that end inside the tree, or go on past it.
+    for (byte, value) in [(13, 0x00), (15, 0xff)] {
+        let mut bad = bytes.clone();
+        bad[byte] = value;
+        assert!(Archive::new(Cursor::new(bad)).unwrap().tree(1).is_err());
+    }
+}
+
+#[test]
+fn version_1() {
+    let tree = Tree::from_rolls("200000").unwrap();
+    let mut writer = ArchiveWriter::new(Vec::new(), None).unwrap();
+    writer.push(&tree, true).unwrap();
+    let mut bytes = writer.finish().unwrap();
+    // Drop the censored bit: the 17 bits of 9 nodes still fit in 3 bytes.
+    bytes[8..12].copy_from_slice(&1u32.to_le_bytes());
+    let record = u32::from_le_bytes([bytes[13], bytes[14], bytes[15], 0]) >> 1;
+    bytes[13..16].copy_from_slice(&record.to_le_bytes()[..3]);
+    let mut archive = Archive::new(Cursor::new(bytes)).unwrap();
+    // Only the footer counts the censored trees, which is enough for one.
+    assert_eq!(archive.tree(1).unwrap(), Generated::Censored(tree));
+}
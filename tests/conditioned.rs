@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use thin_tree::{Offspring, OffspringDistribution, Size, SizeError, Tree, TreeGenerator};
+
+fn sized(law: Offspring, size: Size, seed: u64) -> Result<Tree, SizeError> {
+    let mut generator = TreeGenerator::from(law);
+    generator.seed = seed;
+    generator.generate_sized(size)
+}
+
+#[test]
+fn exact_sizes() {
+    let law = Offspring::BernoulliPair { n: 50, m: 100 };
+    for seed in 0..50 {
+        for nodes in [7, 8, 9, 20, 333] {
+            let tree = sized(law.clone(), Size::Nodes(nodes), seed).unwrap();
+            assert_eq!(tree.counts().nodes, nodes as u64);
+            assert!(tree.has_fixed_generations());
+        }
+        for leaves in [4, 5, 17, 200] {
+            let tree = sized(law.clone(), Size::Leaves(leaves), seed).unwrap();
+            assert_eq!(tree.counts().leaves, leaves as u64);
+            assert!(tree.has_fixed_generations());
+            assert_eq!(Tree::from_rolls(&tree.rolls()).unwrap(), tree);
+        }
+    }
+    let law = Offspring::Binomial { p: 0.3 };
+    for seed in 0..20 {
+        assert_eq!(
+            sized(law.clone(), Size::Nodes(64), seed)
+                .unwrap()
+                .counts()
+                .nodes,
+            64
+        );
+        assert_eq!(
+            sized(law.clone(), Size::Leaves(9), seed)
+                .unwrap()
+                .counts()
+                .leaves,
+            9
+        );
+    }
+    // Same seed, same tree.
+    assert_eq!(
+        sized(law.clone(), Size::Nodes(500), 7),
+        sized(law, Size::Nodes(500), 7)
+    );
+}
+
+/// Level-order child counts of every forest of four trees with `n` nodes.
+fn forests(n: usize) -> Vec<Vec<u32>> {
+    let mut words = vec![(Vec::new(), 0i64)];
+    for _ in 0..n {
+        words = words
+            .into_iter()
+            .flat_map(|(word, sum)| {
+                (0..=2).filter_map(move |k| {
+                    let sum = sum + k as i64 - 1;
+                    let mut word = word.clone();
+                    word.push(k);
+                    (sum > -4 || (sum == -4 && word.len() == n)).then_some((word, sum))
+                })
+            })
+            .collect();
+    }
+    words
+        .into_iter()
+        .filter(|&(_, sum)| sum == -4)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+#[test]
+fn nodes_distribution() {
+    let law = Offspring::BernoulliPair { n: 50, m: 100 };
+    let forests = forests(8);
+    let weight = |word: &[u32]| -> f64 { word.iter().map(|&k| law.probability(k)).product() };
+    let total: f64 = forests.iter().map(|word| weight(word)).sum();
+    let expected: HashMap<String, f64> = forests
+        .iter()
+        .map(|word| {
+            let rolls = word.iter().map(|k| k.to_string()).collect();
+            (rolls, weight(word) / total)
+        })
+        .collect();
+    let trials = 100_000;
+    let mut seen = HashMap::<String, u32>::new();
+    for seed in 0..trials {
+        let tree = sized(law.clone(), Size::Nodes(11), seed).unwrap();
+        *seen.entry(tree.rolls()).or_default() += 1;
+    }
+    assert!(seen.keys().all(|rolls| expected.contains_key(rolls)));
+    let distance: f64 = expected
+        .iter()
+        .map(|(rolls, p)| (seen.get(rolls).copied().unwrap_or(0) as f64 / trials as f64 - p).abs())
+        .sum::<f64>()
+        / 2.0;
+    assert!(distance < 0.02, "{distance} over {} forests", forests.len());
+}
+
+#[test]
+fn leaves_distribution() {
+    // Only nodes of 0 and 2 children: the size is fixed by the leaves.
+    let tree = sized(Offspring::Table(vec![1.0, 0.0, 1.0]), Size::Leaves(10), 3).unwrap();
+    assert_eq!((tree.counts().leaves, tree.counts().nodes), (10, 19));
+    // Otherwise the nodes of one child are negative binomial, over
+    // 2L - 4 failures.
+    let law = Offspring::BernoulliPair { n: 50, m: 100 };
+    let (leaves, trials) = (12u32, 20_000);
+    let p1 = law.probability(1);
+    let expected = 3.0 + (2 * leaves - 4) as f64 + (2 * leaves - 4) as f64 * p1 / (1.0 - p1);
+    let mean = (0..trials)
+        .map(|seed| {
+            sized(law.clone(), Size::Leaves(leaves), seed)
+                .unwrap()
+                .counts()
+                .nodes as f64
+        })
+        .sum::<f64>()
+        / trials as f64;
+    assert!((mean - expected).abs() < 0.1, "{mean} against {expected}");
+}
+
+#[test]
+fn errors() {
+    let law = Offspring::BernoulliPair { n: 50, m: 100 };
+    assert_eq!(
+        sized(Offspring::Poisson { lambda: 0.5 }, Size::Nodes(10), 0),
+        Err(SizeError::Law)
+    );
+    assert_eq!(
+        sized(law.clone(), Size::Nodes(6), 0),
+        Err(SizeError::TooSmall {
+            min: Size::Nodes(7)
+        })
+    );
+    assert_eq!(
+        sized(law, Size::Leaves(3), 0),
+        Err(SizeError::TooSmall {
+            min: Size::Leaves(4)
+        })
+    );
+    // Full binary forests of four trees have an even number of nodes.
+    let full = Offspring::Table(vec![1.0, 0.0, 1.0]);
+    assert_eq!(
+        sized(full.clone(), Size::Nodes(12), 0),
+        Err(SizeError::Impossible)
+    );
+    assert_eq!(sized(full, Size::Nodes(13), 0).unwrap().counts().nodes, 13);
+    // Without leaves, no tree ends.
+    assert_eq!(
+        sized(Offspring::Table(vec![0.0, 1.0]), Size::Nodes(10), 0),
+        Err(SizeError::Impossible)
+    );
+    assert_eq!(
+        sized(Offspring::Table(vec![1.0, 1.0]), Size::Leaves(5), 0),
+        Err(SizeError::Impossible)
+    );
+    assert_eq!(
+        SizeError::TooSmall {
+            min: Size::Leaves(4)
+        }
+        .to_string(),
+        "every tree has at least 4 leaves"
+    );
+}
+
+#[test]
+fn large() {
+    let tree = sized(
+        Offspring::BernoulliPair { n: 50, m: 100 },
+        Size::Nodes(1_000_000),
+        1,
+    )
+    .unwrap();
+    assert_eq!(tree.counts().nodes, 1_000_000);
+    let tree = sized(Offspring::Binomial { p: 0.5 }, Size::Leaves(300_000), 1).unwrap();
+    assert_eq!(tree.counts().leaves, 300_000);
+}
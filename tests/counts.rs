@@ -0,0 +1,64 @@
+use proptest::prelude::*;
+use thin_tree::{csv, csv::TreeFile, Offspring, TreeGenerator};
+
+proptest! {
+    #[test]
+    fn counts_match_trees(
+        lambda in 0.0f64..3.0,
+        seed: u64,
+        max_nodes in 1u32..5000,
+        max_generations in 0u32..40,
+    ) {
+        let generator = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(max_nodes)
+            .max_generations(max_generations);
+        let (tree, counts) = (generator.generate(), generator.simulate());
+        prop_assert_eq!(tree.is_censored(), counts.is_censored());
+        prop_assert_eq!(tree.tree().counts(), counts.counts());
+    }
+}
+
+#[test]
+fn counts_only_file() {
+    let generator = TreeGenerator::new(70, 100).seed(9).max_nodes(300);
+    let dir = std::env::temp_dir();
+    let (full, counts) = (
+        dir.join("thin-tree-full.csv"),
+        dir.join("thin-tree-counts-only.csv"),
+    );
+    let censored = csv::write_samples(&full, &generator, 50, true).unwrap();
+    assert_eq!(
+        csv::write_samples(&counts, &generator, 50, false).unwrap(),
+        censored
+    );
+    let (full, counts) = (
+        std::fs::read_to_string(&full).unwrap(),
+        std::fs::read_to_string(&counts).unwrap(),
+    );
+    for (row, counts_row) in full.lines().zip(counts.lines()).skip(2).take(50) {
+        let counts: Vec<&str> = row.split(',').take(6).collect();
+        assert_eq!(counts.join(","), counts_row);
+    }
+    assert_eq!(full.lines().last(), counts.lines().last());
+    let file = TreeFile::parse(&counts).unwrap();
+    assert!(file.is_samples() && !file.has_rolls());
+    assert_eq!(file.len(), 50);
+    assert!(file.tree(1).is_err());
+}
+
+#[test]
+fn heavy_tails() {
+    // Child counts near u32::MAX are censored before the arena indexes them.
+    for max_nodes in [1_000_000, u32::MAX] {
+        for seed in 0..20 {
+            let generator = TreeGenerator::from(Offspring::Geometric { p: 1e-12 })
+                .seed(seed)
+                .max_nodes(max_nodes);
+            let tree = generator.generate();
+            assert!(tree.is_censored());
+            assert_eq!(tree.tree().counts().nodes, 7);
+            assert_eq!(tree.tree().counts(), generator.simulate().counts());
+        }
+    }
+}
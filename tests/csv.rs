@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use thin_tree::{
+    csv::{self, LoadError, TreeFile},
+    Generated, RngStrategy, TreeGenerator,
+};
+
+#[test]
+fn tree_file_round_trip() {
+    let generator = TreeGenerator::new(55, 100).rng(RngStrategy::Secure).seed(7);
+    let tree = generator.generate().into_tree();
+    let contents = format!(
+        "{}{}\n",
+        csv::header(Some(&generator), false),
+        csv::record(&tree)
+    );
+    let file = TreeFile::parse(&contents).unwrap();
+    assert!(!file.is_samples());
+    assert_eq!(file.version, csv::VERSION);
+    assert_eq!(file.origin, Some(generator.clone()));
+    assert_eq!(file.tree(1).unwrap(), Generated::Complete(tree.clone()));
+    let reseeding = generator.reseed(4096);
+    let header = csv::header(Some(&reseeding), false);
+    assert!(header.ends_with(",reseed=4096\n"));
+    let file = TreeFile::parse(&format!("{header}{}\n", csv::record(&tree))).unwrap();
+    assert_eq!(file.origin, Some(reseeding));
+    // A law no generator can draw from is not a setting.
+    let invalid = TreeGenerator::new(200, 100).seed(7);
+    let header = csv::header(Some(&invalid), false);
+    assert!(matches!(
+        TreeFile::parse(&format!("{header}{}\n", csv::record(&tree))),
+        Err(LoadError::Format(_))
+    ));
+}
+
+#[test]
+fn sample_rows() {
+    let file = TreeFile::parse(
+        "# n=50,m=100,rng=Fast,seed=42\n\
+         0,leaves,branches,nodes,generations,rolls\n\
+         1,8,7,15,5,011001001000\n\
+         2,4,3,7,2,0000\n",
+    )
+    .unwrap();
+    assert!(file.is_samples());
+    assert_eq!(file.version, 1);
+    assert_eq!(file.len(), 2);
+    assert_eq!(file.tree(2).unwrap().tree().counts().leaves, 4);
+    assert!(matches!(file.tree(3), Err(LoadError::NoRow(3))));
+}
+
+#[test]
+fn mismatched_counts() {
+    let file = TreeFile::parse("4,3,7,3,0000\n").unwrap();
+    assert!(matches!(
+        file.tree(1),
+        Err(LoadError::Mismatch {
+            column: "generations",
+            stored: 3,
+            actual: 2
+        })
+    ));
+}
+
+#[test]
+fn interrupted_and_resumed() {
+    let generator = TreeGenerator::new(60, 100).seed(3).max_nodes(500);
+    let dir = std::env::temp_dir();
+    let (whole, part) = (
+        dir.join("thin-tree-whole.csv"),
+        dir.join("thin-tree-part.csv"),
+    );
+    let censored = csv::write_samples(&whole, &generator, 3000, true).unwrap();
+    let whole = std::fs::read_to_string(&whole).unwrap();
+    assert!(whole.ends_with(&format!("# rows=3000,censored={censored}\n")));
+
+    let cancel = AtomicBool::new(false);
+    let progress = csv::stream_samples(&part, &generator, 3000, true, &cancel, |_| {
+        cancel.store(true, Ordering::Relaxed)
+    })
+    .unwrap();
+    assert!(progress.interrupted && progress.rows < 3000);
+    let contents = std::fs::read_to_string(&part).unwrap();
+    assert!(contents.ends_with(",interrupted\n"));
+    let file = TreeFile::parse(&contents).unwrap();
+    assert!(file.interrupted);
+    assert_eq!(file.len(), progress.rows as usize);
+
+    let cancel = AtomicBool::new(false);
+    let progress = csv::resume_samples(&part, 3000, &cancel, |_| {}).unwrap();
+    assert_eq!((progress.rows, progress.censored), (3000, censored));
+    assert_eq!(std::fs::read_to_string(&part).unwrap(), whole);
+
+    // A run killed mid-row leaves neither a trailer nor a whole last row.
+    std::fs::write(&part, &whole[..whole.len() / 2]).unwrap();
+    csv::resume_samples(&part, 3000, &cancel, |_| {}).unwrap();
+    assert_eq!(std::fs::read_to_string(&part).unwrap(), whole);
+}
+
+#[test]
+fn censored_rows() {
+    let generator = TreeGenerator::new(70, 100).seed(4).max_nodes(60);
+    let path = std::env::temp_dir().join("thin-tree-censored-rows.csv");
+    let censored = csv::write_samples(&path, &generator, 200, true).unwrap();
+    assert!(censored > 0 && censored < 200);
+    let file = TreeFile::read(&path).unwrap();
+    for (i, tree) in file.trees().enumerate() {
+        let drawn = generator.generate_with(&mut generator.sample_rng(i as u64));
+        assert_eq!(tree.unwrap(), drawn);
+    }
+    assert_eq!(
+        file.trees()
+            .filter(|t| t.as_ref().unwrap().is_censored())
+            .count(),
+        censored as usize
+    );
+    let packed = std::env::temp_dir().join("thin-tree-censored-rows.ttb");
+    thin_tree::archive::from_csv(&path, &packed).unwrap();
+    let mut archive = thin_tree::archive::Archive::open(&packed).unwrap();
+    assert_eq!(archive.censored, censored);
+    for row in 1..=200 {
+        assert_eq!(archive.tree(row).unwrap(), file.tree(row).unwrap());
+    }
+
+    // Version 3 rows have no flag, and read as complete.
+    let file = TreeFile::parse(
+        "# version=3\n\
+         0,leaves,branches,nodes,generations,sackin,colless,cherries,path_length,strahler,width,rolls\n\
+         1,4,3,7,2,8,0,2,10,3,4,0000\n\
+         # rows=1,censored=1\n",
+    )
+    .unwrap();
+    assert!(!file.tree(1).unwrap().is_censored());
+    assert_eq!(file.censored_rows, Some(1));
+    assert!(TreeFile::parse(
+        "# version=4\n0,censored,leaves,branches,nodes,generations\n1,2,4,3,7,2\n"
+    )
+    .is_err());
+}
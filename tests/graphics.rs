@@ -0,0 +1,87 @@
+use proptest::prelude::*;
+use thin_tree::{
+    csv,
+    graphics::{dot_header, layout, svg_header, GENERATION_COLOURS},
+    Offspring, Tree, TreeGenerator,
+};
+
+proptest! {
+    #[test]
+    fn tidy(lambda in 0.0f64..3.0, seed: u64) {
+        let tree = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(3000)
+            .generate()
+            .into_tree();
+        let xs = layout(&tree);
+        prop_assert_eq!(xs.iter().fold(f64::MAX, |min, &x| min.min(x)), 0.0);
+        for gen in 0..=tree.counts().generations as u32 {
+            let level: Vec<f64> = tree.generation(gen).map(|node| xs[node as usize]).collect();
+            for pair in level.windows(2) {
+                prop_assert!(pair[1] - pair[0] >= 1.0 - 1e-9);
+            }
+        }
+        for node in 0..tree.counts().nodes as u32 {
+            let children = tree.children(node);
+            if !children.is_empty() {
+                let middle = (xs[children.start as usize] + xs[children.end as usize - 1]) / 2.0;
+                prop_assert!((xs[node as usize] - middle).abs() < 1e-9);
+            }
+        }
+    }
+}
+
+#[test]
+fn layout_of_a_small_tree() {
+    let tree = Tree::from_rolls("0300000").unwrap();
+    assert_eq!(
+        layout(&tree),
+        [1.5, 0.5, 2.5, 0.0, 1.0, 2.0, 3.0, 0.0, 1.0, 2.0]
+    );
+    // Halves summed down a deep tree used to leave the leftmost node just
+    // below 0.
+    let tree = TreeGenerator::from(Offspring::Poisson {
+        lambda: 1.0094232989342502,
+    })
+    .seed(10012009238104144663)
+    .max_nodes(3000)
+    .generate()
+    .into_tree();
+    assert_eq!(layout(&tree).into_iter().fold(f64::MAX, f64::min), 0.0);
+}
+
+#[test]
+fn pictures() {
+    let tree = Tree::from_rolls("020000").unwrap();
+    let dot = tree.to_dot();
+    assert!(dot.starts_with("digraph tree {\n"));
+    assert_eq!(
+        dot.matches(" -> ").count(),
+        tree.counts().nodes as usize - 1
+    );
+    assert!(dot.contains(&format!("n8 [fillcolor=\"{}\"]", GENERATION_COLOURS[3])));
+    let svg = tree.to_svg();
+    assert_eq!(svg.matches("<circle").count(), tree.counts().nodes as usize);
+    assert_eq!(
+        svg.matches("<line").count(),
+        tree.counts().nodes as usize - 1
+    );
+}
+
+#[test]
+fn settings() {
+    let generator = TreeGenerator::from(Offspring::Geometric { p: 0.4 }).seed(12);
+    let fields = csv::header(Some(&generator), false)[2..]
+        .trim_end()
+        .to_owned();
+    assert!(fields.contains(",seed=12,"));
+    assert_eq!(
+        dot_header(Some(&generator), false),
+        format!("// {fields}\n")
+    );
+    assert_eq!(
+        svg_header(Some(&generator), false),
+        format!("<!-- {fields} -->\n")
+    );
+    assert_eq!(svg_header(None, true), "<!-- version=4,censored -->\n");
+}
@@ -0,0 +1,55 @@
+use thin_tree::{csv, Histogram, Sample, TreeGenerator};
+
+#[test]
+fn bins() {
+    let histogram = Histogram::new(&[3, 4, 4, 9, 12], 4);
+    assert_eq!((histogram.start, histogram.width), (3, 3));
+    assert_eq!(histogram.counts, vec![3, 0, 1, 1]);
+    assert_eq!(
+        histogram.bins().collect::<Vec<_>>(),
+        vec![(3, 5, 3), (6, 8, 0), (9, 11, 1), (12, 14, 1)]
+    );
+}
+
+#[test]
+fn fewer_values_than_bins() {
+    let histogram = Histogram::new(&[7, 8, 8], 20);
+    assert_eq!(histogram.width, 1);
+    assert_eq!(histogram.counts, vec![1, 2]);
+    assert!(Histogram::new(&[], 20).counts.is_empty());
+    assert_eq!(Histogram::new(&[u32::MAX as u64, 0], 0).counts, vec![2]);
+}
+
+#[test]
+fn whole_range() {
+    let histogram = Histogram::new(&[u64::MAX, 0, u64::MAX / 2], 4);
+    let bins: Vec<_> = histogram.bins().collect();
+    assert_eq!(bins.len(), 4);
+    assert_eq!(bins[0].0, 0);
+    assert_eq!(bins[3].1, u64::MAX);
+    assert_eq!(histogram.counts, vec![1, 1, 0, 1]);
+}
+
+#[test]
+fn in_files() {
+    let generator = TreeGenerator::new(50, 100).seed(6);
+    let histograms = Sample::draw(&generator, 200).histograms(5);
+    let path = std::env::temp_dir().join("thin-tree-histograms.csv");
+    csv::write_histograms(&path, &histograms, &generator, 200).unwrap();
+    let file = std::fs::read_to_string(&path).unwrap();
+    let mut lines = file.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        format!(
+            "{},samples=200",
+            csv::header(Some(&generator), false).trim_end()
+        )
+    );
+    assert_eq!(lines.next(), Some("metric,from,to,count"));
+    let leaves: u32 = lines
+        .filter_map(|line| line.strip_prefix("Leaves,"))
+        .map(|bin| bin.rsplit(',').next().unwrap().parse::<u32>().unwrap())
+        .sum();
+    assert!(leaves > 0 && leaves <= 200);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "serde")]
+use thin_tree::{
+    check_stats,
+    json::{self, Document, JsonError, Settings},
+    Node, Offspring, RngStrategy, Tree, TreeGenerator,
+};
+
+#[test]
+fn round_trips() {
+    let generator = TreeGenerator::from(Offspring::Table(vec![1.0, 0.5, 2.0]))
+        .rng(RngStrategy::Secure)
+        .seed(11)
+        .max_nodes(400);
+    let tree = generator.generate();
+    let settings = Settings {
+        generator: generator.clone(),
+        sample_size: 200,
+    };
+    let stats = check_stats(&generator, 200, &[5.0, 95.0]);
+    for document in [
+        Document::Tree {
+            tree: tree.tree().clone(),
+            censored: tree.is_censored(),
+            origin: Some(generator.clone()),
+        },
+        Document::Settings(settings.clone()),
+        Document::Stats {
+            settings,
+            stats: Box::new(stats),
+        },
+    ] {
+        assert_eq!(
+            json::from_json(&json::to_json(&document)).unwrap(),
+            document
+        );
+    }
+}
+
+#[test]
+fn older_stats() {
+    let settings = Settings {
+        generator: TreeGenerator::new(50, 100).seed(3),
+        sample_size: 100,
+    };
+    let stats = check_stats(&settings.generator, 100, &[]);
+    let mut value: serde_json::Value = serde_json::from_str(&json::to_json(&Document::Stats {
+        settings,
+        stats: Box::new(stats.clone()),
+    }))
+    .unwrap();
+    // Metrics added since the first version of the schema.
+    for metric in ["sackin", "profile"] {
+        value["stats"].as_object_mut().unwrap().remove(metric);
+    }
+    let Document::Stats { stats: old, .. } = json::from_json(&value.to_string()).unwrap() else {
+        panic!("not stats");
+    };
+    assert_eq!(old.leaves, stats.leaves);
+    assert_eq!(old.sackin, Default::default());
+    assert!(old.profile.generations.is_empty());
+}
+
+#[test]
+fn layout() {
+    let json = json::to_json(&Document::Settings(Settings {
+        generator: TreeGenerator::new(50, 100).seed(3),
+        sample_size: 10,
+    }));
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], json::VERSION);
+    assert_eq!(value["kind"], "settings");
+    assert_eq!(value["generator"]["offspring"]["bernoulli_pair"]["n"], 50);
+    assert_eq!(value["generator"]["rng"], "fast");
+
+    let tree = Tree::from_rolls("020000").unwrap();
+    assert_eq!(serde_json::to_string(&tree).unwrap(), "[2,2,2,0,2,0,0,0,0]");
+    let node: Node = serde_json::from_str("[2,2,2,0,2,0,0,0,0]").unwrap();
+    assert_eq!(node, tree.to_node());
+}
+
+#[test]
+fn errors() {
+    for json in [
+        r#"{"kind":"settings"}"#,
+        r#"{"version":2,"kind":"tree","tree":[0],"censored":false,"origin":null}"#,
+        r#"{"version":1,"kind":"tree","tree":[2,0],"censored":false,"origin":null}"#,
+        r#"{"version":1,"kind":"tree","tree":[1,0,0],"censored":false,"origin":null}"#,
+    ] {
+        assert!(json::from_json(json).is_err(), "{json}");
+    }
+    assert!(matches!(
+        json::from_json(r#"{"version":2,"kind":"settings"}"#),
+        Err(JsonError::Version(2))
+    ));
+}
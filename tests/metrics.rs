@@ -0,0 +1,57 @@
+use proptest::prelude::*;
+use thin_tree::{
+    render::tree_to_string,
+    tree::{count_branches, count_generations, count_leaves},
+    Offspring, Tree, TreeGenerator, TreeMetrics,
+};
+
+proptest! {
+    #[test]
+    fn same_as_separate_walks(lambda in 0.0f64..12.0, seed: u64) {
+        // Large means give nodes with ten or more children, rolled as `[k]`.
+        let tree = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(3000)
+            .generate()
+            .into_tree();
+        let metrics = TreeMetrics::new(&tree);
+        prop_assert_eq!(
+            TreeMetrics::without_rolls(&tree),
+            TreeMetrics { rolls: String::new(), ..metrics.clone() }
+        );
+        let node = tree.to_node();
+        prop_assert_eq!(metrics.counts, tree.counts());
+        prop_assert_eq!(metrics.counts.leaves, count_leaves(&node));
+        prop_assert_eq!(metrics.counts.branches, count_branches(&node));
+        prop_assert_eq!(metrics.counts.generations, count_generations(&node));
+        prop_assert_eq!(&metrics.rolls, &tree_to_string(&tree, false));
+        prop_assert_eq!(Tree::from_rolls(&metrics.rolls), Ok(tree));
+        prop_assert_eq!(&metrics.shape.widths, &metrics.profile.widths().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn tall_tree() {
+    // One child each, until the generation limit censors the line.
+    let tree = TreeGenerator::from(Offspring::Table(vec![0.0, 1.0]))
+        .max_generations(200_000)
+        .generate()
+        .into_tree();
+    let metrics = TreeMetrics::new(&tree);
+    assert_eq!(metrics.counts.generations, 200_000);
+    assert_eq!(metrics.counts.leaves, 4);
+    assert_eq!(metrics.shape.strahler, 3);
+    assert_eq!(metrics.shape.cherries, 0);
+    assert_eq!(metrics.rolls.len(), 4 * (200_000 - 1));
+    assert!(metrics.rolls.ends_with("11110000"));
+    assert_eq!(metrics.profile.leaves.last(), Some(&4));
+}
+
+#[test]
+fn wide_rolls() {
+    let tree = Tree::from_rolls("0[12]00000000000000").unwrap();
+    let metrics = TreeMetrics::new(&tree);
+    assert_eq!(metrics.rolls, "0[12]00000000000000");
+    assert_eq!(metrics.counts.nodes, 7 + 12);
+    assert_eq!(TreeMetrics::new(&Tree::default()).rolls, "");
+}
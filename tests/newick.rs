@@ -0,0 +1,93 @@
+use proptest::prelude::*;
+use thin_tree::{csv, newick, Generated, NewickError, Offspring, Tree, TreeGenerator};
+
+proptest! {
+    #[test]
+    fn round_trip(lambda in 0.0f64..3.0, seed: u64, labels: bool, lengths: bool) {
+        let tree = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(5000)
+            .generate()
+            .into_tree();
+        let (read, origin) = Tree::from_newick(&tree.to_newick(labels, lengths)).unwrap();
+        prop_assert_eq!(read.into_tree(), tree);
+        prop_assert_eq!(origin, None);
+    }
+}
+
+#[test]
+fn writing() {
+    let tree = Tree::from_rolls("0300000").unwrap();
+    assert_eq!(tree.to_newick(false, false), "((,(,,)),(,));");
+    assert_eq!(
+        Tree::from_rolls("0000").unwrap().to_newick(true, true),
+        "((n3:1,n4:1)n1:1,(n5:1,n6:1)n2:1)n0;"
+    );
+}
+
+#[test]
+fn reading() {
+    let (tree, _) =
+        Tree::from_newick("(A:0.1, 'B ''b''':2e-1, (C, D, E)[three]F:.3, (G)) root ;\n").unwrap();
+    let tree = tree.into_tree();
+    assert_eq!(tree.child_count(0), 4);
+    assert_eq!(tree.counts().leaves, 6);
+    assert_eq!(tree.counts().generations, 2);
+    assert!(!tree.has_fixed_generations());
+    assert_eq!(
+        Tree::from_newick(";").unwrap(),
+        (Generated::Complete(Tree::default()), None)
+    );
+}
+
+#[test]
+fn settings() {
+    let generator = TreeGenerator::from(Offspring::Poisson { lambda: 1.5 })
+        .seed(9)
+        .max_nodes(40);
+    let tree = generator.generate();
+    let text =
+        newick::header(Some(&generator), tree.is_censored()) + &tree.tree().to_newick(true, false);
+    assert_eq!(
+        text.lines().next().unwrap(),
+        format!(
+            "[{}]",
+            csv::header(Some(&generator), tree.is_censored()).trim_end()
+        )
+    );
+    assert_eq!(Tree::from_newick(&text).unwrap(), (tree, Some(generator)));
+    // Without settings, the comment still gives the version.
+    let (tree, origin) = Tree::from_newick("[# version=4]\n(,);").unwrap();
+    assert_eq!((tree.tree().counts().leaves, origin), (2, None));
+    // Other comments are not settings.
+    assert_eq!(Tree::from_newick("[&R] (,);").unwrap().1, None);
+}
+
+#[test]
+fn errors() {
+    for (newick, error) in [
+        ("((,),(,))", NewickError::MissingSemicolon),
+        ("((,),(,)", NewickError::Unclosed { depth: 1 }),
+        (
+            "((,),(,)));",
+            NewickError::Unexpected {
+                position: 9,
+                found: ')',
+            },
+        ),
+        ("(,);(,);", NewickError::Trailing { position: 4 }),
+        ("('a,b);", NewickError::Unterminated { position: 1 }),
+        ("(a:x,b);", NewickError::Length { position: 3 }),
+        (
+            "[# version=4,(,);",
+            NewickError::Unterminated { position: 0 },
+        ),
+        (
+            "[# version=9](,);",
+            NewickError::Header("file is from a newer version"),
+        ),
+        ("[# version=4](,)", NewickError::MissingSemicolon),
+    ] {
+        assert_eq!(Tree::from_newick(newick), Err(error), "{newick}");
+    }
+}
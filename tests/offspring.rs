@@ -0,0 +1,81 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use thin_tree::{Offspring, OffspringDistribution};
+
+fn laws() -> Vec<Offspring> {
+    vec![
+        Offspring::BernoulliPair { n: 50, m: 100 },
+        Offspring::Binomial { p: 0.3 },
+        Offspring::Geometric { p: 0.6 },
+        Offspring::Poisson { lambda: 1.3 },
+        Offspring::Table(vec![1.0, 0.0, 2.0, 1.0]),
+    ]
+}
+
+#[test]
+fn probabilities_sum_to_one() {
+    for law in laws() {
+        let total: f64 = (0..200).map(|k| law.probability(k)).sum();
+        assert!((total - 1.0).abs() < 1e-9, "{law}: {total}");
+    }
+}
+
+#[test]
+fn sample_mean_matches() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    for law in laws() {
+        let draws = 200_000;
+        let mean = (0..draws).map(|_| law.sample(&mut rng) as f64).sum::<f64>() / draws as f64;
+        assert!((mean - law.mean()).abs() < 0.02, "{law}: {mean}");
+    }
+}
+
+#[test]
+fn spec_round_trip() {
+    for law in laws() {
+        assert_eq!(law.to_string().parse::<Offspring>(), Ok(law));
+    }
+    assert!("binomial:1.5".parse::<Offspring>().is_err());
+    assert!("bernoulli-pair:3/2".parse::<Offspring>().is_err());
+}
+
+#[test]
+fn closed_form_moments() {
+    for law in laws() {
+        let mean: f64 = (0..200).map(|k| k as f64 * law.probability(k)).sum();
+        let variance: f64 = (0..200)
+            .map(|k| (k as f64 - mean).powi(2) * law.probability(k))
+            .sum();
+        assert!((law.mean() - mean).abs() < 1e-9, "{law}: {mean}");
+        assert!(
+            (law.variance() - variance).abs() < 1e-9,
+            "{law}: {variance}"
+        );
+    }
+    // Far more mass than a series of 10,000 terms covers.
+    let geometric = Offspring::Geometric { p: 1e-6 };
+    assert!((geometric.mean() - 999_999.0).abs() < 1e-6);
+    assert!((geometric.variance() / 999_999e6 - 1.0).abs() < 1e-12);
+    let poisson = Offspring::Poisson { lambda: 1e5 };
+    assert_eq!((poisson.mean(), poisson.variance()), (1e5, 1e5));
+}
+
+#[test]
+fn large_poisson() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    for lambda in [10.0, 37.5, 1000.0, 50_000.0] {
+        let law = Offspring::Poisson { lambda };
+        let total: f64 = (0..200_000).map(|k| law.probability(k)).sum();
+        assert!((total - 1.0).abs() < 1e-9, "{law}: {total}");
+        let draws = 100_000;
+        let sample: Vec<f64> = (0..draws).map(|_| law.sample(&mut rng) as f64).collect();
+        let mean = sample.iter().sum::<f64>() / draws as f64;
+        let variance = sample.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / draws as f64;
+        // Within 5 standard errors.
+        assert!(
+            (mean - lambda).abs() < 5.0 * (lambda / draws as f64).sqrt(),
+            "{law}: {mean}"
+        );
+        assert!((variance / lambda - 1.0).abs() < 0.03, "{law}: {variance}");
+    }
+}
@@ -0,0 +1,97 @@
+use thin_tree::{
+    check_stats, csv, MeanProfile, Offspring, Prediction, Profile, Tree, TreeGenerator,
+};
+
+#[test]
+fn one_tree() {
+    let profile = Profile::new(&Tree::from_rolls("0300000").unwrap());
+    assert_eq!(profile.leaves, [0, 0, 3, 3]);
+    assert_eq!(profile.branches, [1, 2, 1, 0]);
+    assert_eq!(profile.widths().collect::<Vec<_>>(), [1, 2, 4, 3]);
+}
+
+#[test]
+fn mean_with_bands() {
+    let profiles = [
+        Profile {
+            leaves: vec![0, 2],
+            branches: vec![1, 0],
+        },
+        Profile {
+            leaves: vec![1],
+            branches: vec![0],
+        },
+    ];
+    let mean = MeanProfile::new(&profiles);
+    assert_eq!(mean.samples, 2);
+    let [root, next] = &mean.generations[..] else {
+        panic!("{mean:?}");
+    };
+    assert_eq!((root.leaves, root.branches, root.width), (0.5, 0.5, 1.0));
+    assert_eq!((root.std_dev, root.lower, root.upper), (0.0, 1.0, 1.0));
+    // Widths 2 and 0, the shorter tree counting as empty.
+    assert_eq!((next.leaves, next.width, next.std_dev), (1.0, 1.0, 1.0));
+    let margin = 1.96 / 2f64.sqrt();
+    assert!((next.lower - (1.0 - margin)).abs() < 1e-12);
+    assert!((next.upper - (1.0 + margin)).abs() < 1e-12);
+    assert!(MeanProfile::new(&[]).generations.is_empty());
+    // Wide generations keep their small spread.
+    let wide = |leaves| Profile {
+        leaves: vec![leaves],
+        branches: vec![0],
+    };
+    let mean = MeanProfile::new(&[wide(u32::MAX), wide(u32::MAX - 2)]);
+    assert_eq!(mean.generations[0].width, (u32::MAX - 1) as f64);
+    assert_eq!(mean.generations[0].std_dev, 1.0);
+}
+
+#[test]
+fn against_theory() {
+    let law = Offspring::Poisson { lambda: 0.8 };
+    let prediction = Prediction::new(&law);
+    assert!((prediction.growth - 0.8).abs() < 1e-9);
+    for (gen, width) in [1.0, 2.0, 4.0, 3.2].into_iter().enumerate() {
+        assert!((prediction.width(gen as u32) - width).abs() < 1e-9);
+    }
+    // Conditioned on dying out: Binomial(2, 1/4), mean 1/2.
+    let prediction = Prediction::new(&Offspring::Binomial { p: 0.75 });
+    assert!((prediction.growth - 0.5).abs() < 1e-12);
+
+    let generator = TreeGenerator::from(law.clone()).seed(4);
+    let stats = check_stats(&generator, 4000, &[]);
+    let profile = &stats.profile;
+    assert_eq!(profile.samples, 4000);
+    let nodes: f64 = profile.generations.iter().map(|g| g.width).sum();
+    assert!((nodes - stats.nodes.mean).abs() < 1e-9);
+    assert_eq!(profile.generations.len() as u64, stats.generations.max + 1);
+    for gen in 3..8 {
+        let mean = &profile.generations[gen];
+        let expected = Prediction::new(&law).width(gen as u32);
+        // Four standard errors either side, so the test does not flake.
+        let margin = 2.0 * (mean.upper - mean.lower);
+        assert!((mean.width - expected).abs() < margin, "{gen}: {mean:?}");
+    }
+
+    let path = std::env::temp_dir().join("thin-tree-profile.csv");
+    csv::write_profile(&path, profile, &Prediction::new(&law), &generator, 4000).unwrap();
+    let file = std::fs::read_to_string(&path).unwrap();
+    let mut lines = file.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        format!(
+            "{},samples=4000",
+            csv::header(Some(&generator), false).trim_end()
+        )
+    );
+    assert_eq!(
+        lines.next(),
+        Some("generation,leaves,branches,width,std_dev,lower,upper,expected")
+    );
+    assert_eq!(lines.next(), Some("0,0,1,1,0,1,1,1"));
+    assert!(lines.nth(1).unwrap().starts_with("2,"));
+    assert!(lines.last().unwrap().ends_with(&format!(
+        ",{}",
+        Prediction::new(&law).width(stats.generations.max as u32)
+    )));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,97 @@
+use proptest::prelude::*;
+use thin_tree::{Offspring, RollsError, Tree, TreeGenerator};
+
+fn law() -> impl Strategy<Value = Offspring> {
+    prop_oneof![
+        (1u32..1000, 0.0f64..=1.0).prop_map(|(m, n_frac)| Offspring::BernoulliPair {
+            n: (m as f64 * n_frac) as u32,
+            m
+        }),
+        (0.05f64..=1.0).prop_map(|p| Offspring::Geometric { p }),
+        (0.0f64..4.0).prop_map(|lambda| Offspring::Poisson { lambda }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn rolls_round_trip(law in law(), seed: u64, max_nodes in 1u32..5000) {
+        let tree = TreeGenerator::from(law)
+            .seed(seed)
+            .max_nodes(max_nodes)
+            .generate()
+            .into_tree();
+        prop_assert_eq!(Tree::from_rolls(&tree.rolls()), Ok(tree));
+    }
+}
+
+#[test]
+fn smallest_tree() {
+    let tree = Tree::from_rolls("0000").unwrap();
+    let counts = tree.counts();
+    assert_eq!(
+        (counts.leaves, counts.branches, counts.generations),
+        (4, 3, 2)
+    );
+}
+
+#[test]
+fn truncated() {
+    assert_eq!(
+        Tree::from_rolls("00200"),
+        Err(RollsError::Truncated {
+            generation: 3,
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        Tree::from_rolls(""),
+        Err(RollsError::Truncated {
+            generation: 2,
+            expected: 4,
+            found: 0
+        })
+    );
+}
+
+#[test]
+fn trailing() {
+    assert_eq!(
+        Tree::from_rolls("000000"),
+        Err(RollsError::Trailing { position: 4 })
+    );
+}
+
+#[test]
+fn invalid_char() {
+    assert_eq!(
+        Tree::from_rolls("00x0"),
+        Err(RollsError::InvalidChar {
+            position: 2,
+            found: 'x'
+        })
+    );
+}
+
+#[test]
+fn wide_nodes() {
+    let tree = Tree::from_rolls(&format!("0[12]01{}", "0".repeat(13))).unwrap();
+    assert_eq!((tree.counts().leaves, tree.counts().nodes), (15, 20));
+    assert_eq!(Tree::from_rolls(&tree.rolls()), Ok(tree));
+    assert_eq!(
+        Tree::from_rolls("0[12"),
+        Err(RollsError::InvalidChar {
+            position: 1,
+            found: '['
+        })
+    );
+}
+
+#[test]
+fn binary_rolls() {
+    assert_eq!(
+        Tree::from_binary_rolls("001000").unwrap(),
+        Tree::from_rolls("002000").unwrap()
+    );
+    assert!(Tree::from_binary_rolls("0020").is_err());
+}
@@ -0,0 +1,77 @@
+use proptest::prelude::*;
+use thin_tree::{
+    csv::{LoadError, TreeFile},
+    Node, Offspring, Sample, Shape, Tree, TreeGenerator,
+};
+
+proptest! {
+    #[test]
+    fn consistent(lambda in 0.0f64..3.0, seed: u64) {
+        let tree = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(3000)
+            .generate()
+            .into_tree();
+        let shape = Shape::new(&tree);
+        prop_assert_eq!(shape.widths.iter().map(|&w| w as u64).sum::<u64>(), tree.counts().nodes);
+        prop_assert_eq!(shape.widths.len() as u64, tree.counts().generations + 1);
+        prop_assert!(shape.sackin <= shape.path_length);
+        prop_assert!(shape.cherries as u64 * 2 <= tree.counts().leaves);
+        prop_assert!(1u64 << (shape.strahler - 1) <= tree.counts().leaves);
+    }
+}
+
+#[test]
+fn known_shapes() {
+    // ((,(,,)),(,)) with a ternary node.
+    let tree = Tree::from_rolls("0300000").unwrap();
+    let shape = Shape::new(&tree);
+    assert_eq!(shape.sackin, 2 + 3 * 3 + 2 + 2);
+    assert_eq!(shape.path_length, 2 + 4 * 2 + 3 * 3);
+    // |1 - 3| at the root's left child, and 4 - 2 at the root.
+    assert_eq!(shape.colless, 2 + 2);
+    assert_eq!(shape.cherries, 1);
+    assert_eq!(shape.strahler, 3);
+    assert_eq!(shape.widths, [1, 2, 4, 3]);
+    assert_eq!(shape.width(), 4);
+    assert!((shape.average_path_length() - 19.0 / 10.0).abs() < 1e-12);
+
+    // Pairwise differences for more than two children.
+    let star = Tree::from(Node::Branch(vec![
+        Node::Leaf,
+        Node::Branch(vec![Node::Leaf, Node::Leaf]),
+        Node::Branch(vec![Node::Branch(vec![Node::Leaf; 3])]),
+    ]));
+    let shape = Shape::new(&star);
+    assert_eq!(shape.colless, (2 - 1) + (3 - 1) + (3 - 2));
+    assert_eq!(shape.cherries, 1);
+    assert_eq!(shape.strahler, 3);
+    assert_eq!(Shape::new(&Tree::default()).strahler, 1);
+}
+
+#[test]
+fn in_files_and_samples() {
+    let file = TreeFile::parse("# version=3\n4,3,7,2,8,0,2,10,3,4,0000\n").unwrap();
+    assert_eq!(
+        file.tree(1).unwrap().into_tree(),
+        Tree::from_rolls("0000").unwrap()
+    );
+    let file = TreeFile::parse("# version=3\n4,3,7,2,8,1,2,10,3,4,0000\n").unwrap();
+    assert!(matches!(
+        file.tree(1),
+        Err(LoadError::Mismatch {
+            column: "colless",
+            stored: 1,
+            actual: 0
+        })
+    ));
+
+    let generator = TreeGenerator::new(55, 100).seed(2).max_nodes(2000);
+    let sample = Sample::draw(&generator, 100);
+    let first = Shape::new(generator.generate().tree());
+    assert_eq!(sample.sackin[0], first.sackin);
+    assert_eq!(sample.width[0], first.width() as u64);
+    let stats = sample.stats(&[]);
+    assert!(stats.sackin.mean <= stats.path_length.mean);
+    assert!(stats.cherries.max as f64 * 2.0 <= stats.leaves.max as f64);
+}
@@ -0,0 +1,121 @@
+use rand::RngCore;
+use thin_tree::{check_stats, Offspring, RngStrategy, Sample, Summary, TreeGenerator, TreeRng};
+
+#[test]
+fn summary() {
+    let summary = Summary::new(&mut [5, 3, 1, 4, 2], &[0.0, 90.0]);
+    assert_eq!(
+        (
+            summary.min,
+            summary.q1,
+            summary.median,
+            summary.q3,
+            summary.max
+        ),
+        (1, 2, 3, 4, 5)
+    );
+    assert_eq!(summary.mean, 3.0);
+    assert!((summary.std_dev - 2f64.sqrt()).abs() < 1e-12);
+    assert!(summary.skewness.abs() < 1e-12);
+    assert!((summary.kurtosis + 1.3).abs() < 1e-12);
+    assert_eq!(summary.percentiles, vec![(0.0, 1), (90.0, 5)]);
+    let flat = Summary::new(&mut [7; 4], &[]);
+    assert_eq!(
+        (flat.std_dev, flat.skewness, flat.kurtosis),
+        (0.0, 0.0, 0.0)
+    );
+    assert_eq!(Summary::new(&mut [], &[50.0]).percentiles, vec![(50.0, 0)]);
+}
+
+#[test]
+fn adversarial_summaries() {
+    // Fractional means, which integer division would truncate.
+    assert_eq!(Summary::new(&mut [1, 2], &[]).mean, 1.5);
+
+    // A spread of 1 to 5 on a huge offset, where the sum of squares would
+    // lose it: E[x^2] - E[x]^2 cancels to nothing at 10^15.
+    for offset in [1u64 << 50, 1_000_000_000_000_000, u64::MAX - 5] {
+        let mut values: Vec<u64> = (0..1000).map(|i| offset + 1 + i % 5).collect();
+        let summary = Summary::new(&mut values, &[]);
+        assert_eq!(summary.mean, (offset + 3) as f64, "{offset}");
+        assert!((summary.std_dev - 2f64.sqrt()).abs() < 1e-12, "{summary:?}");
+        assert!(summary.skewness.abs() < 1e-12, "{summary:?}");
+        assert!((summary.kurtosis + 1.3).abs() < 1e-12, "{summary:?}");
+    }
+
+    // Sums far past u32 and u64.
+    let mut values = vec![u32::MAX as u64; 100_000];
+    let summary = Summary::new(&mut values, &[]);
+    assert_eq!((summary.mean, summary.std_dev), (u32::MAX as f64, 0.0));
+    let mut values = vec![u64::MAX; 1000];
+    values.extend([0; 1000]);
+    let summary = Summary::new(&mut values, &[50.0]);
+    assert_eq!(summary.mean, 2f64.powi(63));
+    assert!((summary.std_dev / 2f64.powi(63) - 1.0).abs() < 1e-12);
+    assert_eq!(summary.percentiles, vec![(50.0, u64::MAX)]);
+
+    // One outlier among many equal values, checked against closed forms:
+    // with n - 1 zeros and a one, the skewness is (n - 2) / sqrt(n - 1), and
+    // the excess kurtosis n^2 / (n - 1) - 6.
+    let n = 1_000_000u64;
+    let mut values = vec![0; n as usize - 1];
+    values.push(1);
+    let summary = Summary::new(&mut values, &[]);
+    let n = n as f64;
+    assert!((summary.mean - 1.0 / n).abs() < 1e-18);
+    assert!((summary.std_dev - ((n - 1.0) / (n * n)).sqrt()).abs() < 1e-15);
+    let skewness = (n - 2.0) / (n - 1.0).sqrt();
+    assert!((summary.skewness - skewness).abs() / skewness < 1e-9);
+    let kurtosis = n * n / (n - 1.0) - 6.0;
+    assert!((summary.kurtosis - kurtosis).abs() / kurtosis < 1e-9);
+}
+
+#[test]
+fn every_metric() {
+    let stats = check_stats(&TreeGenerator::new(60, 100).seed(5), 500, &[]);
+    assert_eq!(stats.samples, 500);
+    assert!(
+        (stats.leaves.mean + stats.branches.mean - stats.nodes.mean).abs() < 1e-9,
+        "{stats:?}"
+    );
+    assert!(stats.generations.min >= 2);
+    assert!(stats.nodes.min >= 7);
+}
+
+#[test]
+fn independent_of_threads() {
+    let generator = TreeGenerator::from(Offspring::Poisson { lambda: 0.9 }).seed(11);
+    let draw = |threads| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| Sample::draw(&generator, 2000))
+    };
+    let sample = draw(1);
+    assert_eq!(draw(4), sample);
+    assert_eq!(draw(7), sample);
+    assert_eq!(
+        sample.leaves[0],
+        generator.generate().tree().counts().leaves
+    );
+}
+
+#[test]
+fn reseeding() {
+    let generator = TreeGenerator::new(50, 100)
+        .seed(2)
+        .rng(RngStrategy::Secure)
+        .reseed(256);
+    assert!(!generator.is_reproducible());
+    assert!(generator.clone().reseed(0).is_reproducible());
+    assert!(generator.clone().rng(RngStrategy::Fast).is_reproducible());
+    // Every RNG made on a thread is a handle on that thread's one.
+    let (mut first, mut second) = (generator.make_rng(), generator.make_rng());
+    assert!(matches!(
+        (&first, &second),
+        (TreeRng::Reseeding(a), TreeRng::Reseeding(b)) if a == b
+    ));
+    assert_ne!(first.next_u64(), second.next_u64());
+    assert_eq!(Sample::draw(&generator, 300).samples, 300);
+}
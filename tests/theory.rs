@@ -0,0 +1,57 @@
+use thin_tree::{theory::Regime, Offspring, Prediction, TreeGenerator};
+
+#[test]
+fn subcritical() {
+    let prediction = Prediction::new(&Offspring::Poisson { lambda: 0.5 });
+    assert_eq!(prediction.regime, Regime::Subcritical);
+    assert!((prediction.variance - 0.5).abs() < 1e-9);
+    assert_eq!(prediction.extinction, 1.0);
+    assert!((prediction.nodes.unwrap() - 11.0).abs() < 1e-9);
+    assert!((prediction.leaves.unwrap() - 8.0 * (-0.5f64).exp()).abs() < 1e-9);
+}
+
+#[test]
+fn critical() {
+    let prediction = Prediction::new(&Offspring::Binomial { p: 0.5 });
+    assert_eq!(prediction.regime, Regime::Critical);
+    assert_eq!(prediction.finite, 1.0);
+    assert_eq!(prediction.nodes, None);
+}
+
+#[test]
+fn supercritical() {
+    // (1/4 + 3s/4)^2 = s at s = 1/9.
+    let prediction = Prediction::new(&Offspring::Binomial { p: 0.75 });
+    assert_eq!(prediction.regime, Regime::Supercritical);
+    assert!((prediction.extinction - 1.0 / 9.0).abs() < 1e-12);
+    // Conditioned on extinction: Binomial(2, 1/4), mean 1/2.
+    assert!((prediction.nodes.unwrap() - 11.0).abs() < 1e-9);
+    assert!((prediction.leaves.unwrap() - 4.0 * 0.5625 * 2.0).abs() < 1e-9);
+    assert_eq!(
+        Prediction::new(&Offspring::Table(vec![0.0, 1.0, 1.0])).finite,
+        0.0
+    );
+}
+
+#[test]
+fn matches_samples() {
+    let sample = |law: Offspring, max_nodes| {
+        let generator = TreeGenerator::from(law).seed(3).max_nodes(max_nodes);
+        let mut rng = generator.make_rng();
+        let finite: Vec<u64> = (0..5_000)
+            .map(|_| generator.generate_with(&mut rng))
+            .filter(|tree| !tree.is_censored())
+            .map(|tree| tree.tree().counts().nodes)
+            .collect();
+        (
+            finite.len() as f64 / 5_000.0,
+            finite.iter().sum::<u64>() as f64 / finite.len() as f64,
+        )
+    };
+    let (_, nodes) = sample(Offspring::Poisson { lambda: 0.5 }, 1_000_000);
+    assert!((nodes - 11.0).abs() < 0.3, "{nodes}");
+    let law = Offspring::Binomial { p: 0.6 };
+    let prediction = Prediction::new(&law);
+    let (finite, _) = sample(law, 1000);
+    assert!((finite - prediction.finite).abs() < 0.01, "{finite}");
+}
@@ -0,0 +1,53 @@
+use proptest::prelude::*;
+use thin_tree::{
+    render::print_tree,
+    tree::{count_branches, count_generations, count_leaves, count_nodes},
+    Node, Offspring, Tree, TreeGenerator,
+};
+
+proptest! {
+    #[test]
+    fn boxed_round_trip(lambda in 0.0f64..3.0, seed: u64, max_nodes in 1u32..5000) {
+        let tree = TreeGenerator::from(Offspring::Poisson { lambda })
+            .seed(seed)
+            .max_nodes(max_nodes)
+            .generate()
+            .into_tree();
+        let node = tree.to_node();
+        let counts = tree.counts();
+        prop_assert_eq!(counts.leaves, count_leaves(&node));
+        prop_assert_eq!(counts.branches, count_branches(&node));
+        prop_assert_eq!(counts.nodes, count_nodes(&node));
+        prop_assert_eq!(counts.generations, count_generations(&node));
+        prop_assert_eq!(Tree::from(node), tree);
+    }
+}
+
+#[test]
+fn navigation() {
+    let tree = Tree::from_rolls("0300000").unwrap();
+    assert_eq!(tree.generation(2), 3..7);
+    assert_eq!(tree.generation(3), 7..10);
+    assert_eq!(tree.children(4), 7..10);
+    assert_eq!(tree.child_count(0), 2);
+    assert!(tree.children(9).is_empty());
+}
+
+#[test]
+fn drawing() {
+    let tree = Tree::from_rolls("020000").unwrap();
+    assert_eq!(
+        print_tree(&tree),
+        "Root\n\
+         ╠Branch\n\
+         ║╠Leaf\n\
+         ║╚Branch\n\
+         ║ ╠Leaf\n\
+         ║ ╚Leaf\n\
+         ╚Branch\n \
+         ╠Leaf\n \
+         ╚Leaf\n"
+    );
+    assert_eq!(print_tree(&Tree::default()), "╚Leaf\n");
+    assert_eq!(Tree::default().to_node(), Node::Leaf);
+}